@@ -0,0 +1,366 @@
+use crate::epoch::{Common, DROPBOX, Guard};
+use crate::sync::{self, AtomicPtr, Ordering};
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::ptr::{self, NonNull};
+
+/// The low bits of a `*mut T` which alignment keeps at zero and which can
+/// therefore carry a tag, such as a mark for logical deletion.
+pub const fn tag_mask<T>() -> usize {
+    mem::align_of::<T>() - 1
+}
+
+/// Splits a stored word into the pointer and its tag.
+pub(crate) fn decompose<T>(raw: *mut T) -> (*mut T, usize) {
+    let mask = tag_mask::<T>();
+    (raw.map_addr(|addr| addr & !mask), raw.addr() & mask)
+}
+
+/// Puts `tag` into the low bits of `ptr`, bits that do not fit are dropped.
+pub(crate) fn compose<T>(ptr: *mut T, tag: usize) -> *mut T {
+    let mask = tag_mask::<T>();
+    ptr.map_addr(|addr| (addr & !mask) | (tag & mask))
+}
+
+/// A heap allocated value that is not shared with any other thread yet.
+/// This is the only thing an `Atomic` accepts, which is what makes it
+/// impossible to store a pointer that `DropBox` cannot free.
+pub struct Owned<T> {
+    ptr: NonNull<T>,
+    _marker: PhantomData<Box<T>>,
+}
+
+unsafe impl<T: Send> Send for Owned<T> {}
+unsafe impl<T: Sync> Sync for Owned<T> {}
+
+impl<T> Owned<T> {
+    pub fn new(value: T) -> Self {
+        Self::from(Box::new(value))
+    }
+
+    pub fn into_box(self) -> Box<T> {
+        let ptr = self.into_raw();
+        // SAFETY:
+        //    The pointer was created from a Box and the
+        //    ownership has just been given up by `self`.
+        unsafe { Box::from_raw(ptr) }
+    }
+
+    pub(crate) fn as_raw(&self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+
+    pub(crate) fn into_raw(self) -> *mut T {
+        let ptr = self.ptr.as_ptr();
+        mem::forget(self);
+        ptr
+    }
+}
+
+impl<T> From<Box<T>> for Owned<T> {
+    fn from(boxed: Box<T>) -> Self {
+        Self {
+            // SAFETY:
+            //    A Box never holds a null pointer.
+            ptr: unsafe { NonNull::new_unchecked(Box::into_raw(boxed)) },
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Deref for Owned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY:
+        //    The pointer is valid and uniquely owned by `self`.
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T> DerefMut for Owned<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY:
+        //    The pointer is valid and uniquely owned by `self`.
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T> Drop for Owned<T> {
+    fn drop(&mut self) {
+        // SAFETY:
+        //    The pointer was created from a Box and nobody
+        //    else has ever seen it.
+        let _ = unsafe { Box::from_raw(self.ptr.as_ptr()) };
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Owned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Owned").field(&**self).finish()
+    }
+}
+
+/// A pointer loaded inside of a critical section. It cannot outlive
+/// the guard it was loaded through. It may carry a tag in the bits that
+/// the alignment of `T` leaves unused, see `tag_mask`. Two `Shared` are
+/// only equal if their tags are equal as well.
+pub struct Shared<'g, T> {
+    ptr: *mut T,
+    _marker: PhantomData<&'g T>,
+}
+
+impl<T> Clone for Shared<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Shared<'_, T> {}
+
+impl<T> PartialEq for Shared<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.ptr, other.ptr)
+    }
+}
+
+impl<T> Eq for Shared<'_, T> {}
+
+impl<T> fmt::Debug for Shared<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Shared").field(&self.ptr).finish()
+    }
+}
+
+impl<'g, T> Shared<'g, T> {
+    pub(crate) fn from_raw(ptr: *mut T) -> Self {
+        Self {
+            ptr,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn null() -> Self {
+        Self::from_raw(ptr::null_mut())
+    }
+
+    /// The pointer without its tag.
+    pub fn as_ptr(&self) -> *mut T {
+        decompose(self.ptr).0
+    }
+
+    /// The pointer together with its tag, the way it is stored.
+    pub fn as_raw(&self) -> *mut T {
+        self.ptr
+    }
+
+    /// Whether the pointer is null, whatever its tag.
+    pub fn is_null(&self) -> bool {
+        self.as_ptr().is_null()
+    }
+
+    pub fn tag(&self) -> usize {
+        decompose(self.ptr).1
+    }
+
+    /// The same pointer with its tag replaced by `tag`.
+    pub fn with_tag(&self, tag: usize) -> Self {
+        Self::from_raw(compose(self.ptr, tag))
+    }
+
+    /// # Safety
+    ///
+    /// The pointer must either be null or point to a valid T
+    /// which was not retired before the guard was pinned. Every guard
+    /// that retires values from where it was loaded must come from the
+    /// same collector as this one, as a collector only waits for its own
+    /// guards. `Atomic` checks the latter for you.
+    pub unsafe fn as_ref(&self) -> Option<&'g T> {
+        // SAFETY:
+        //    Upheld by the caller.
+        unsafe { self.as_ptr().as_ref() }
+    }
+}
+
+/// Returned by the `compare_exchange` methods when the current value did not
+/// match. The value that was found is handed out along with the new value
+/// which was never stored.
+pub struct CompareExchangeError<'g, T> {
+    pub current: Shared<'g, T>,
+    pub new: Owned<T>,
+}
+
+impl<T> fmt::Debug for CompareExchangeError<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompareExchangeError")
+            .field("current", &self.current)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An epoch aware atomic pointer. It owns the value it points to, only
+/// accepts `Owned` values and only hands out `Shared` pointers tied to
+/// a guard. Values that get replaced are retired through `DropBox`, so
+/// they stay readable until every guard that could have seen them is gone.
+/// The stored pointer can carry a tag, which is kept by `load` and
+/// compared by `compare_exchange`. This is the only way to replace shared
+/// values from safe code, the raw `AtomicPtr` methods of `Worker` and `Guard`
+/// cannot know what the pointer holds.
+///
+/// Values are always retired through the default collector, so every
+/// method that takes a guard panics if it was not obtained from it, by
+/// `epoch::pin` or a worker of `Registration::create_register`. A guard
+/// of another collector would not hold back their reclamation.
+pub struct Atomic<T> {
+    ptr: AtomicPtr<T>,
+    _marker: PhantomData<Box<T>>,
+}
+
+unsafe impl<T: Send + Sync> Send for Atomic<T> {}
+unsafe impl<T: Send + Sync> Sync for Atomic<T> {}
+
+impl<T> Atomic<T> {
+    pub fn new(value: T) -> Self {
+        Self::from(Owned::new(value))
+    }
+
+    sync::const_fn! {
+        pub fn null() -> Self {
+            Self {
+                ptr: AtomicPtr::new(ptr::null_mut()),
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    pub fn load<'g>(&self, guard: &'g Guard) -> Shared<'g, T> {
+        assert!(guard.is_default(), "guard of another collector");
+        guard.load(&self.ptr)
+    }
+
+    /// Loads the value and borrows it for as long as both the guard and
+    /// the `Atomic` live, or `None` if the pointer is null. This is the
+    /// read path that needs no unsafe code.
+    pub fn get<'g>(&'g self, guard: &'g Guard) -> Option<&'g T> {
+        let shared = self.load(guard);
+        // SAFETY:
+        //    Only `Owned` values are ever stored, and replaced ones are
+        //    retired through the default collector, which `load` checked
+        //    the guard belongs to. The current value is only freed when
+        //    the `Atomic` is dropped, which the borrow of `self` prevents.
+        unsafe { shared.as_ref() }
+    }
+
+    /// Sets the bits of `tag` in the tag of the stored pointer and returns
+    /// the previous value. See `Guard::fetch_or`.
+    pub fn fetch_or<'g>(&self, tag: usize, guard: &'g Guard) -> Shared<'g, T> {
+        assert!(guard.is_default(), "guard of another collector");
+        guard.fetch_or(&self.ptr, tag)
+    }
+}
+
+impl<T: Send + 'static> Atomic<T> {
+    /// Stores `new` and retires the previous value. The previous value
+    /// is returned and can still be read for as long as the guard lives.
+    pub fn swap<'g>(&self, new: Owned<T>, guard: &'g Guard) -> Shared<'g, T> {
+        assert!(guard.is_default(), "guard of another collector");
+        let old = self.ptr.swap(new.into_raw(), Ordering::AcqRel);
+        guard.defer_reclaim(decompose(old).0 as *mut dyn Common, &DROPBOX);
+        Shared::from_raw(old)
+    }
+
+    pub fn store(&self, new: Owned<T>, guard: &Guard) {
+        self.swap(new, guard);
+    }
+
+    /// Stores `new` only if the current value, tag included, is still
+    /// `current`. On success the replaced value is retired and returned, on
+    /// failure the value that was found and `new` itself are handed back.
+    pub fn compare_exchange<'g>(
+        &self,
+        current: Shared<'_, T>,
+        new: Owned<T>,
+        guard: &'g Guard,
+    ) -> Result<Shared<'g, T>, CompareExchangeError<'g, T>> {
+        assert!(guard.is_default(), "guard of another collector");
+        let raw = new.as_raw();
+        match self
+            .ptr
+            .compare_exchange(current.ptr, raw, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(old) => {
+                mem::forget(new);
+                guard.defer_reclaim(decompose(old).0 as *mut dyn Common, &DROPBOX);
+                Ok(Shared::from_raw(old))
+            }
+            Err(found) => Err(CompareExchangeError {
+                current: Shared::from_raw(found),
+                new,
+            }),
+        }
+    }
+
+    /// Keeps calling `f` with the freshly loaded value and tries to store
+    /// what it returns until a store succeeds or `f` returns None. On success
+    /// the replaced value is retired and returned, otherwise the value `f`
+    /// gave up on is returned as the error.
+    pub fn fetch_update<'g, F>(
+        &self,
+        guard: &'g Guard,
+        mut f: F,
+    ) -> Result<Shared<'g, T>, Shared<'g, T>>
+    where
+        F: FnMut(Shared<'g, T>) -> Option<Owned<T>>,
+    {
+        let mut current = self.load(guard);
+        loop {
+            let Some(new) = f(current) else {
+                return Err(current);
+            };
+            match self.compare_exchange(current, new, guard) {
+                Ok(old) => return Ok(old),
+                Err(err) => current = err.current,
+            }
+        }
+    }
+}
+
+impl<T> From<Owned<T>> for Atomic<T> {
+    fn from(owned: Owned<T>) -> Self {
+        Self {
+            ptr: AtomicPtr::new(owned.into_raw()),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for Atomic<T> {
+    fn default() -> Self {
+        Self::null()
+    }
+}
+
+impl<T> Drop for Atomic<T> {
+    fn drop(&mut self) {
+        // Loom atomics have no `get_mut`, having `&mut self` makes the
+        // ordering irrelevant anyway.
+        let ptr = decompose(self.ptr.load(Ordering::Relaxed)).0;
+        if !ptr.is_null() {
+            // SAFETY:
+            //    Only Owned values are ever stored, so the pointer came
+            //    from a Box. Having `&mut self` means that nobody can
+            //    load it anymore.
+            let _ = unsafe { Box::from_raw(ptr) };
+        }
+    }
+}
+
+impl<T> fmt::Debug for Atomic<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Atomic")
+            .field(&self.ptr.load(Ordering::Relaxed))
+            .finish()
+    }
+}
@@ -0,0 +1,13 @@
+//! Lock-free data structures built on the collector. Nodes that get
+//! unlinked are retired through the garbage lists of the default worker
+//! of the calling thread, see `epoch::pin`.
+
+mod list;
+mod map;
+mod queue;
+mod stack;
+
+pub use self::list::ListSet;
+pub use self::map::HashMap;
+pub use self::queue::Queue;
+pub use self::stack::Stack;
@@ -0,0 +1,290 @@
+use crate::atomic::Shared;
+use crate::epoch::{DROPBOX, Guard, pin};
+use crate::sync::{self, AtomicPtr, Ordering};
+use std::borrow::Borrow;
+use std::cmp::Ordering as Cmp;
+use std::marker::PhantomData;
+use std::ptr;
+
+// Tag on a `next` pointer saying that its node has been logically deleted.
+// Once it is set the pointer never changes again.
+const MARK: usize = 1;
+
+/// A node of a sorted Harris-Michael list. The list functions below take
+/// the link to start from, so a search can also start in the middle of a
+/// list, at a node that is never removed.
+pub(crate) struct Node<T> {
+    pub(crate) value: T,
+    next: AtomicPtr<Node<T>>,
+}
+
+impl<T> Node<T> {
+    pub(crate) fn new(value: T) -> Box<Self> {
+        Box::new(Self {
+            value,
+            next: AtomicPtr::new(ptr::null_mut()),
+        })
+    }
+
+    /// The link to start from to search the rest of the list.
+    pub(crate) fn next(&self) -> &AtomicPtr<Node<T>> {
+        &self.next
+    }
+}
+
+/// Where a search stopped: `curr` is the first node that is not smaller
+/// than the key, or null, and `prev` is the link pointing to it.
+pub(crate) struct Cursor<'g, T> {
+    prev: &'g AtomicPtr<Node<T>>,
+    curr: Shared<'g, Node<T>>,
+    found: bool,
+}
+
+impl<'g, T> Cursor<'g, T> {
+    /// The node with an equal key, if the search found one.
+    pub(crate) fn found(&self) -> Option<&'g Node<T>> {
+        if !self.found {
+            return None;
+        }
+        // SAFETY:
+        //    A found node is not null and protected by the guard of the
+        //    search.
+        unsafe { self.curr.as_ref() }
+    }
+}
+
+/// Looks for the first node for which `cmp` is not `Less`, unlinking and
+/// retiring the marked nodes it comes across. `cmp` compares a node key
+/// with the key searched for.
+pub(crate) fn find<'g, T, F>(
+    head: &'g AtomicPtr<Node<T>>,
+    cmp: F,
+    guard: &'g Guard,
+) -> Cursor<'g, T>
+where
+    T: Send + 'static,
+    F: Fn(&T) -> Cmp,
+{
+    'retry: loop {
+        let mut prev = head;
+        // Links are only followed once they were found unmarked, so `curr`
+        // never carries a tag and a CAS expecting it fails as soon as the
+        // node owning `prev` gets marked.
+        let mut curr = guard.load(prev).with_tag(0);
+        loop {
+            // SAFETY:
+            //    Nodes are retired only after they are unlinked, so
+            //    whatever was reachable when the guard loaded it stays
+            //    alive until the guard is dropped.
+            let Some(node) = (unsafe { curr.as_ref() }) else {
+                return Cursor {
+                    prev,
+                    curr,
+                    found: false,
+                };
+            };
+            let next = guard.load(&node.next);
+            if next.tag() == MARK {
+                let next = next.with_tag(0);
+                if guard.compare_exchange_shared(prev, curr, next).is_err() {
+                    continue 'retry;
+                }
+                // SAFETY:
+                //    Only the CAS that unlinks a node retires it, and the
+                //    node came from a Box.
+                unsafe { guard.retire(curr.as_ptr(), &DROPBOX) };
+                curr = next;
+                continue;
+            }
+            match cmp(&node.value) {
+                Cmp::Less => {
+                    prev = &node.next;
+                    curr = next;
+                }
+                order => {
+                    return Cursor {
+                        prev,
+                        curr,
+                        found: order == Cmp::Equal,
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// Links `node` in front of the first node that is not smaller according
+/// to `cmp`, which compares a node key with the key of `node`. If a node
+/// with an equal key is found instead, `node` is handed back.
+pub(crate) fn insert<'g, T, F>(
+    head: &'g AtomicPtr<Node<T>>,
+    node: Box<Node<T>>,
+    cmp: F,
+    guard: &'g Guard,
+) -> Result<&'g Node<T>, Box<Node<T>>>
+where
+    T: Send + 'static,
+    F: Fn(&T, &T) -> Cmp,
+{
+    let node = Box::into_raw(node);
+    // SAFETY:
+    //    The node is only freed by dropping what is handed back, after the
+    //    last comparison.
+    let value = unsafe { &(*node).value };
+    loop {
+        let cursor = find(head, |v| cmp(v, value), guard);
+        if cursor.found {
+            // SAFETY:
+            //    The node was never published.
+            return Err(unsafe { Box::from_raw(node) });
+        }
+        // SAFETY:
+        //    The node is not published yet.
+        unsafe { (*node).next.store(cursor.curr.as_raw(), Ordering::Relaxed) };
+        let new = Shared::from_raw(node);
+        if guard
+            .compare_exchange_shared(cursor.prev, cursor.curr, new)
+            .is_ok()
+        {
+            // SAFETY:
+            //    The node is published now, so it is only freed after
+            //    being retired, which the guard holds off.
+            return Ok(unsafe { &*node });
+        }
+    }
+}
+
+/// Marks the node with an equal key as deleted and tries to unlink it,
+/// leaving it to later searches if that fails. The node is returned, it
+/// stays readable for as long as the guard lives.
+pub(crate) fn remove<'g, T, F>(
+    head: &'g AtomicPtr<Node<T>>,
+    cmp: F,
+    guard: &'g Guard,
+) -> Option<&'g Node<T>>
+where
+    T: Send + 'static,
+    F: Fn(&T) -> Cmp,
+{
+    loop {
+        let cursor = find(head, &cmp, guard);
+        let node = cursor.found()?;
+        let next = guard.fetch_or(&node.next, MARK);
+        if next.tag() == MARK {
+            // Someone else deleted it first, the next search unlinks it.
+            continue;
+        }
+        if guard
+            .compare_exchange_shared(cursor.prev, cursor.curr, next)
+            .is_ok()
+        {
+            // SAFETY:
+            //    Only the CAS that unlinks a node retires it, and the node
+            //    came from a Box.
+            unsafe { guard.retire(cursor.curr.as_ptr(), &DROPBOX) };
+        } else {
+            find(head, &cmp, guard);
+        }
+        return Some(node);
+    }
+}
+
+/// Frees every node reachable from `head`, marked or not.
+///
+/// SAFETY:
+///    Nobody else may be able to reach the nodes anymore, and those that
+///    were unlinked must not be reachable from `head`.
+pub(crate) unsafe fn free_all<T>(head: &AtomicPtr<Node<T>>) {
+    let mut current = head.load(Ordering::Relaxed);
+    while !current.is_null() {
+        let node = unsafe { Box::from_raw(Shared::from_raw(current).as_ptr()) };
+        current = node.next.load(Ordering::Relaxed);
+    }
+}
+
+/// A sorted set kept in a lock-free linked list after Harris and Michael.
+/// Removing a value first marks its node as deleted, then unlinks it and
+/// retires it, and searches help unlinking marked nodes they run into.
+/// Operations take linear time, which makes the set a building block, for
+/// example for the buckets of a hash map, more than a general purpose
+/// container.
+pub struct ListSet<T> {
+    head: AtomicPtr<Node<T>>,
+    _marker: PhantomData<T>,
+}
+
+// SAFETY:
+//    Values are moved in from any thread and read through shared
+//    references from any thread.
+unsafe impl<T: Send + Sync> Send for ListSet<T> {}
+unsafe impl<T: Send + Sync> Sync for ListSet<T> {}
+
+impl<T> ListSet<T> {
+    sync::const_fn! {
+        pub fn new() -> Self {
+            Self {
+                head: AtomicPtr::new(ptr::null_mut()),
+                _marker: PhantomData,
+            }
+        }
+    }
+}
+
+impl<T: Ord + Send + Sync + 'static> ListSet<T> {
+    /// Adds `value` unless an equal value is already present. Returns
+    /// whether it was added.
+    pub fn insert(&self, value: T) -> bool {
+        let guard = pin();
+        insert(&self.head, Node::new(value), T::cmp, &guard).is_ok()
+    }
+
+    /// Removes the value equal to `value`. Returns whether there was one.
+    pub fn remove<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let guard = pin();
+        remove(&self.head, |k: &T| k.borrow().cmp(value), &guard).is_some()
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(value, &pin()).is_some()
+    }
+
+    /// The value equal to `value`, borrowed for as long as `guard` lives.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not obtained from `epoch::pin`, as removed
+    /// values are retired through the default collector.
+    pub fn get<'g, Q>(&'g self, value: &Q, guard: &'g Guard) -> Option<&'g T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        assert!(guard.is_default(), "guard of another collector");
+        let cursor = find(&self.head, |k: &T| k.borrow().cmp(value), guard);
+        cursor.found().map(|node| &node.value)
+    }
+}
+
+impl<T> Default for ListSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for ListSet<T> {
+    fn drop(&mut self) {
+        // SAFETY:
+        //    No search can run while the set is dropped. A node is only
+        //    retired by the CAS that unlinks it, so what is still linked,
+        //    marked or not, was never retired.
+        unsafe { free_all(&self.head) };
+    }
+}
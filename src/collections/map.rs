@@ -0,0 +1,306 @@
+use super::list::{self, Node};
+use crate::epoch::{Guard, pin};
+use crate::sync::{AtomicIsize, AtomicPtr, AtomicUsize, Ordering};
+use std::borrow::Borrow;
+use std::cmp::Ordering as Cmp;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+use std::ptr;
+
+// The table doubles once there are more than this many entries per bucket.
+const LOAD_FACTOR: usize = 2;
+
+// Bucket `b` lives in segment `usize::BITS - b.leading_zeros()`, so segment
+// 0 holds bucket 0 and segment `i` the `2^(i - 1)` buckets from `2^(i - 1)`
+// on. Segments are only ever added, which lets the table grow without
+// moving buckets around. The table stops growing at `2^(usize::BITS - 1)`
+// buckets, so the last segment is never needed.
+const SEGMENTS: usize = usize::BITS as usize;
+
+/// What the nodes of the map hold. Entries are sorted by their split order
+/// key: the bit reversed hash for regular entries, which have the top bit
+/// of the hash set so that their key is odd, and the bit reversed bucket
+/// index for the sentinels that start every bucket.
+struct Entry<K, V> {
+    so: usize,
+    kv: Option<(K, V)>,
+}
+
+impl<K: Eq, V> Entry<K, V> {
+    /// Orders the entry against one with split order key `so` and key
+    /// `key`. Entries with the same hash but another key count as smaller,
+    /// so that searches walk the whole run of equal hashes.
+    fn cmp<Q>(&self, so: usize, key: Option<&Q>) -> Cmp
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        match self.so.cmp(&so) {
+            Cmp::Equal => match (&self.kv, key) {
+                (Some((k, _)), Some(key)) if k.borrow() != key => Cmp::Less,
+                _ => Cmp::Equal,
+            },
+            order => order,
+        }
+    }
+}
+
+// Points to a node of the list, also used for the slots of the buckets.
+type Link<K, V> = AtomicPtr<Node<Entry<K, V>>>;
+
+fn regular_key(hash: usize) -> usize {
+    (hash | 1 << (usize::BITS - 1)).reverse_bits()
+}
+
+fn sentinel_key(bucket: usize) -> usize {
+    bucket.reverse_bits()
+}
+
+fn segment_of(bucket: usize) -> (usize, usize) {
+    let segment = (usize::BITS - bucket.leading_zeros()) as usize;
+    let first = (1 << segment) >> 1;
+    (segment, bucket - first)
+}
+
+fn segment_len(segment: usize) -> usize {
+    (1 << segment >> 1).max(1)
+}
+
+/// A concurrent hash map built on split-ordered lists after Shalev and
+/// Shavit. All entries live in one Harris-Michael list sorted by their bit
+/// reversed hash, so splitting a bucket when the table doubles is just a
+/// matter of linking in a new sentinel, and entries never move. Removed
+/// entries are retired through the default collector, which is what lets
+/// `get` hand out references for as long as a guard lives.
+pub struct HashMap<K, V, S = RandomState> {
+    // The sentinel of bucket 0, which is the first node of the list.
+    head: Link<K, V>,
+    segments: [AtomicPtr<Link<K, V>>; SEGMENTS],
+    buckets: AtomicUsize,
+    // Dips below zero while a remove is quicker than the insert it undoes
+    // is to count itself.
+    len: AtomicIsize,
+    hasher: S,
+    _marker: PhantomData<(K, V)>,
+}
+
+// SAFETY:
+//    Keys and values are moved in from any thread and read through shared
+//    references from any thread.
+unsafe impl<K: Send + Sync, V: Send + Sync, S: Send> Send for HashMap<K, V, S> {}
+unsafe impl<K: Send + Sync, V: Send + Sync, S: Sync> Sync for HashMap<K, V, S> {}
+
+impl<K, V> HashMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<K, V, S> HashMap<K, V, S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        let sentinel = Box::into_raw(Node::new(Entry {
+            so: sentinel_key(0),
+            kv: None,
+        }));
+        let map = Self {
+            head: AtomicPtr::new(sentinel),
+            segments: std::array::from_fn(|_| AtomicPtr::new(ptr::null_mut())),
+            buckets: AtomicUsize::new(1),
+            len: AtomicIsize::new(0),
+            hasher,
+            _marker: PhantomData,
+        };
+        map.slot(0).store(sentinel, Ordering::Release);
+        map
+    }
+
+    /// Number of entries, which may already be outdated once it returns.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed).max(0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The slot holding the sentinel of `bucket`, allocating its segment
+    /// if needed.
+    fn slot(&self, bucket: usize) -> &Link<K, V> {
+        let (segment, index) = segment_of(bucket);
+        let mut slots = self.segments[segment].load(Ordering::Acquire);
+        if slots.is_null() {
+            let len = segment_len(segment);
+            let new: Box<[Link<K, V>]> =
+                (0..len).map(|_| AtomicPtr::new(ptr::null_mut())).collect();
+            let new = Box::into_raw(new) as *mut Link<K, V>;
+            match self.segments[segment].compare_exchange(
+                ptr::null_mut(),
+                new,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => slots = new,
+                Err(found) => {
+                    // SAFETY:
+                    //    The segment lost the race and was never shared.
+                    let _ = unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(new, len)) };
+                    slots = found;
+                }
+            }
+        }
+        // SAFETY:
+        //    Segments are only freed when the map is dropped, and the index
+        //    is within the segment.
+        unsafe { &*slots.add(index) }
+    }
+}
+
+impl<K, V, S> HashMap<K, V, S>
+where
+    K: Hash + Eq + Send + Sync + 'static,
+    V: Send + Sync + 'static,
+    S: BuildHasher,
+{
+    fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        self.hasher.hash_one(key) as usize
+    }
+
+    /// The sentinel of the bucket `hash` falls into, linking it and the
+    /// sentinels of its parents first if needed.
+    fn bucket<'g>(&'g self, hash: usize, guard: &'g Guard) -> &'g Node<Entry<K, V>> {
+        let bucket = hash & (self.buckets.load(Ordering::Acquire) - 1);
+        self.sentinel(bucket, guard)
+    }
+
+    fn sentinel<'g>(&'g self, bucket: usize, guard: &'g Guard) -> &'g Node<Entry<K, V>> {
+        let slot = self.slot(bucket);
+        let mut sentinel = slot.load(Ordering::Acquire);
+        if sentinel.is_null() {
+            // The parent is the bucket this one is split off from.
+            let parent = bucket & !(1 << (usize::BITS - 1 - bucket.leading_zeros()));
+            let parent = self.sentinel(parent, guard);
+            let so = sentinel_key(bucket);
+            let node = Node::new(Entry { so, kv: None });
+            let cmp = |e: &Entry<K, V>, _: &Entry<K, V>| e.so.cmp(&so);
+            sentinel = match list::insert(parent.next(), node, cmp, guard) {
+                Ok(node) => node as *const _ as *mut _,
+                Err(_) => {
+                    let cursor = list::find(parent.next(), |e| e.so.cmp(&so), guard);
+                    cursor.found().unwrap() as *const _ as *mut _
+                }
+            };
+            // Whoever got here first stored the same sentinel.
+            let _ = slot.compare_exchange(
+                ptr::null_mut(),
+                sentinel,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            );
+        }
+        // SAFETY:
+        //    Sentinels are never removed, they are freed along with the map.
+        unsafe { &*sentinel }
+    }
+
+    /// Adds `key` with `value` unless the key is already present, in which
+    /// case both are dropped. Returns whether they were added.
+    pub fn insert(&self, key: K, value: V) -> bool {
+        let guard = pin();
+        let hash = self.hash(&key);
+        let so = regular_key(hash);
+        let sentinel = self.bucket(hash, &guard);
+        let node = Node::new(Entry {
+            so,
+            kv: Some((key, value)),
+        });
+        let cmp = |e: &Entry<K, V>, new: &Entry<K, V>| e.cmp(so, new.kv.as_ref().map(|(k, _)| k));
+        if list::insert(sentinel.next(), node, cmp, &guard).is_err() {
+            return false;
+        }
+        let len = self.len.fetch_add(1, Ordering::Relaxed) + 1;
+        let buckets = self.buckets.load(Ordering::Relaxed);
+        if len.max(0) as usize > buckets * LOAD_FACTOR && buckets < 1 << (usize::BITS - 1) {
+            // New buckets are linked in lazily by the first thread that
+            // hashes into them.
+            let _ = self.buckets.compare_exchange(
+                buckets,
+                buckets * 2,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            );
+        }
+        true
+    }
+
+    /// Removes `key`. Returns whether it was present.
+    pub fn remove<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let guard = pin();
+        let hash = self.hash(key);
+        let so = regular_key(hash);
+        let sentinel = self.bucket(hash, &guard);
+        let removed = list::remove(sentinel.next(), |e| e.cmp(so, Some(key)), &guard);
+        if removed.is_some() {
+            self.len.fetch_sub(1, Ordering::Relaxed);
+        }
+        removed.is_some()
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key, &pin()).is_some()
+    }
+
+    /// The value of `key`, borrowed for as long as `guard` lives. Any
+    /// number of lookups can share one guard.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not obtained from `epoch::pin`, as removed
+    /// entries are retired through the default collector.
+    pub fn get<'g, Q>(&'g self, key: &Q, guard: &'g Guard) -> Option<&'g V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        assert!(guard.is_default(), "guard of another collector");
+        let hash = self.hash(key);
+        let so = regular_key(hash);
+        let sentinel = self.bucket(hash, guard);
+        let cursor = list::find(sentinel.next(), |e| e.cmp(so, Some(key)), guard);
+        let (_, value) = cursor.found()?.value.kv.as_ref()?;
+        Some(value)
+    }
+}
+
+impl<K, V, S: Default> Default for HashMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K, V, S> Drop for HashMap<K, V, S> {
+    fn drop(&mut self) {
+        // SAFETY:
+        //    All entries and sentinels hang off the sentinel of bucket 0,
+        //    and no lookup can run while the map is dropped. Sentinels are
+        //    never retired, and entries only by the CAS that unlinks them.
+        unsafe { list::free_all(&self.head) };
+        for (segment, slots) in self.segments.iter().enumerate() {
+            let slots = slots.load(Ordering::Relaxed);
+            if !slots.is_null() {
+                let len = segment_len(segment);
+                // SAFETY:
+                //    The segment was allocated as a boxed slice of `len`.
+                let _ = unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(slots, len)) };
+            }
+        }
+    }
+}
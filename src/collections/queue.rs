@@ -0,0 +1,158 @@
+use crate::epoch::{DROPBOX, pin};
+use crate::sync::{AtomicPtr, Ordering};
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr;
+
+struct Node<T> {
+    // Uninitialized in the sentinel. The `pop` that turns a node into the
+    // sentinel moves its value out.
+    value: MaybeUninit<T>,
+    next: AtomicPtr<Node<T>>,
+}
+
+impl<T> Node<T> {
+    fn boxed(value: MaybeUninit<T>) -> *mut Node<T> {
+        Box::into_raw(Box::new(Node {
+            value,
+            next: AtomicPtr::new(ptr::null_mut()),
+        }))
+    }
+}
+
+/// An unbounded multi producer multi consumer queue after Michael and
+/// Scott. `head` points to a sentinel whose successor holds the first
+/// value, `tail` points to the last node or lags one behind it. Sentinels
+/// that get dequeued are retired, so threads that still look at them can
+/// keep following their `next`.
+pub struct Queue<T> {
+    head: AtomicPtr<Node<T>>,
+    tail: AtomicPtr<Node<T>>,
+    _marker: PhantomData<T>,
+}
+
+// SAFETY:
+//    A value is written before the CAS on `next` that publishes its node,
+//    and read once, by the `pop` whose CAS on `head` makes the node the
+//    sentinel. From then on the slot counts as uninitialized. Producers and
+//    consumers only ever hand a `T` from one to the other.
+unsafe impl<T: Send> Send for Queue<T> {}
+unsafe impl<T: Send> Sync for Queue<T> {}
+
+impl<T> Queue<T> {
+    pub fn new() -> Self {
+        let sentinel = Node::boxed(MaybeUninit::uninit());
+        Self {
+            head: AtomicPtr::new(sentinel),
+            tail: AtomicPtr::new(sentinel),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Whether the queue was empty when it was looked at.
+    pub fn is_empty(&self) -> bool {
+        let guard = pin();
+        let head = guard.load(&self.head);
+        // SAFETY:
+        //    The head is never null and not freed while the guard lives.
+        let head = unsafe { head.as_ref() }.unwrap();
+        head.next.load(Ordering::Acquire).is_null()
+    }
+}
+
+impl<T: Send + 'static> Queue<T> {
+    pub fn push(&self, value: T) {
+        let node = Node::boxed(MaybeUninit::new(value));
+        let guard = pin();
+        loop {
+            let tail = guard.load(&self.tail);
+            // SAFETY:
+            //    The tail is never null, and it is moved past a node before
+            //    that node is dequeued and retired, so the guard keeps it
+            //    alive.
+            let last = unsafe { tail.as_ref() }.unwrap();
+            let next = guard.load(&last.next);
+            if !next.is_null() {
+                // The tail is lagging, help moving it before trying again.
+                let _ = guard.compare_exchange_shared(&self.tail, tail, next);
+                continue;
+            }
+            if last
+                .next
+                .compare_exchange(ptr::null_mut(), node, Ordering::Release, Ordering::Relaxed)
+                .is_ok()
+            {
+                let _ = self.tail.compare_exchange(
+                    tail.as_raw(),
+                    node,
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                );
+                return;
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = pin();
+        loop {
+            let head = guard.load(&self.head);
+            // SAFETY:
+            //    The head is never null and not freed while the guard lives.
+            let sentinel = unsafe { head.as_ref() }.unwrap();
+            let next = guard.load(&sentinel.next);
+            // SAFETY:
+            //    Nodes after the sentinel are only retired once they have
+            //    been the sentinel themselves, which the guard prevents.
+            let first = unsafe { next.as_ref() }?;
+            // The sentinel is about to be retired, the tail must not be
+            // left pointing at it.
+            let tail = guard.load(&self.tail);
+            if tail == head {
+                let _ = guard.compare_exchange_shared(&self.tail, tail, next);
+            }
+            if guard
+                .compare_exchange_shared(&self.head, head, next)
+                .is_ok()
+            {
+                // SAFETY:
+                //    Winning the CAS makes `first` the new sentinel and this
+                //    the only thread that takes its value.
+                let value = unsafe { first.value.assume_init_read() };
+                // SAFETY:
+                //    The old sentinel is unreachable now, came from a Box
+                //    and only the winner of the CAS retires it. Its value
+                //    is uninitialized, so nothing is dropped twice.
+                unsafe { guard.retire(head.as_ptr(), &DROPBOX) };
+                return Some(value);
+            }
+        }
+    }
+}
+
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for Queue<T> {
+    fn drop(&mut self) {
+        // SAFETY:
+        //    Dequeued sentinels are unlinked by the CAS on `head` before
+        //    they are retired, so the nodes from `head` on are the ones the
+        //    queue still owns, and no `push` or `pop` can run while it is
+        //    dropped. They are all boxed by `Node::boxed`. The value of the
+        //    sentinel was moved out or never written, the others still
+        //    hold theirs.
+        unsafe {
+            let sentinel = Box::from_raw(self.head.load(Ordering::Relaxed));
+            let mut current = sentinel.next.load(Ordering::Relaxed);
+            while !current.is_null() {
+                let mut node = Box::from_raw(current);
+                node.value.assume_init_drop();
+                current = node.next.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
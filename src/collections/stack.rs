@@ -0,0 +1,124 @@
+use crate::atomic::Shared;
+use crate::epoch::{DROPBOX, pin};
+use crate::sync::{self, AtomicPtr, Ordering};
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ptr;
+
+struct Node<T> {
+    // Moved out by the `pop` that unlinks the node, so the retired node
+    // must not drop it again.
+    value: ManuallyDrop<T>,
+    // Set before the node is published and never changed afterwards.
+    next: *mut Node<T>,
+}
+
+// SAFETY:
+//    `next` is only written before the node is published, after that the
+//    node is only read through guards and freed by the collector.
+unsafe impl<T: Send> Send for Node<T> {}
+
+/// A Treiber stack. Popped nodes are retired instead of freed, so a
+/// concurrent `pop` that still holds one can read its `next` safely, and
+/// a node cannot be freed and reused at the same address while someone
+/// is about to CAS against it.
+pub struct Stack<T> {
+    head: AtomicPtr<Node<T>>,
+    _marker: PhantomData<T>,
+}
+
+// SAFETY:
+//    Only the `pop` whose CAS on `head` unlinks a node reads its value, and
+//    it returns it by move. Other threads follow `next` of nodes they did
+//    not unlink but never look at their values, so no `&T` is created
+//    outside of `Drop` and `T` does not have to be `Sync`.
+unsafe impl<T: Send> Send for Stack<T> {}
+unsafe impl<T: Send> Sync for Stack<T> {}
+
+impl<T> Stack<T> {
+    sync::const_fn! {
+        pub fn new() -> Self {
+            Self {
+                head: AtomicPtr::new(ptr::null_mut()),
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    /// Whether the stack was empty when it was looked at.
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+}
+
+impl<T: Send + 'static> Stack<T> {
+    pub fn push(&self, value: T) {
+        let node = Box::into_raw(Box::new(Node {
+            value: ManuallyDrop::new(value),
+            next: ptr::null_mut(),
+        }));
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            // SAFETY:
+            //    The node is not published yet.
+            unsafe { (*node).next = head };
+            match self
+                .head
+                .compare_exchange(head, node, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => return,
+                Err(found) => head = found,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = pin();
+        let mut head = guard.load(&self.head);
+        loop {
+            // SAFETY:
+            //    A node loaded through the guard is not freed before the
+            //    guard is dropped, even if another thread pops it.
+            let node = unsafe { head.as_ref() }?;
+            let next = Shared::from_raw(node.next);
+            match guard.compare_exchange_shared(&self.head, head, next) {
+                Ok(_) => {
+                    // SAFETY:
+                    //    Winning the CAS makes this the only thread that
+                    //    takes the value, and the node keeps it wrapped in
+                    //    `ManuallyDrop` so it is not dropped twice.
+                    let value = unsafe { ptr::read(&*node.value) };
+                    // SAFETY:
+                    //    The node is unreachable now, came from a Box and
+                    //    only the winner of the CAS retires it.
+                    unsafe { guard.retire(head.as_ptr(), &DROPBOX) };
+                    return Some(value);
+                }
+                Err(found) => head = found,
+            }
+        }
+    }
+}
+
+impl<T> Default for Stack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for Stack<T> {
+    fn drop(&mut self) {
+        let mut current = self.head.load(Ordering::Relaxed);
+        while !current.is_null() {
+            // SAFETY:
+            //    Popped nodes are unlinked before they are retired, so the
+            //    linked ones are owned by the stack alone, and no `pop` can
+            //    run while it is dropped. `push` boxes every node.
+            let mut node = unsafe { Box::from_raw(current) };
+            // SAFETY:
+            //    The value of a linked node has not been popped.
+            unsafe { ManuallyDrop::drop(&mut node.value) };
+            current = node.next;
+        }
+    }
+}
@@ -1,38 +1,847 @@
 #![allow(unsafe_op_in_unsafe_fn)]
 
-use std::cell::{Cell, RefCell};
+#[cfg(feature = "debug-reclaim")]
+use std::alloc::{Layout, dealloc};
+use std::marker::PhantomData;
 use std::mem;
 use std::ptr::{self, NonNull};
-use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+#[cfg(not(loom))]
+use std::sync::Arc;
+use std::sync::OnceLock;
+#[cfg(not(loom))]
+use std::thread::JoinHandle;
+use std::thread::{self, Thread, ThreadId};
+use std::time::{Duration, Instant};
 
-static EPOCH: Epoch = Epoch::new();
+use crate::atomic::{CompareExchangeError, Owned, Shared, compose, decompose};
+use crate::sync::{
+    self, AtomicBool, AtomicIsize, AtomicPtr, AtomicU64, AtomicUsize, Cell, Mutex, MutexGuard,
+    Ordering, RwLock, atomic,
+};
 
-// Every thread has got two lists. It starts pushing the things
-// into the recent list. One an operation it checks the global epoch
-// if it finds that it has advanced or if the thread itself advances
-// the global epoch, it will deallocate the memory pointed to by the
-// pointers in the PREVIOUS list, make RECENT the previus, and
-// RECENT will have a Vec::new();
-thread_local! {
-    static RECENT: RefCell<List> = const {RefCell::new(List::new())};
-    static PREVIOUS: RefCell<List> = const {RefCell::new(List::new())};
+sync::global! {
+    /// The default collector used by `Registration::find_register`,
+    /// `Registration::create_register` and the thread local default worker.
+    fn default_collector() -> &'static Collector {
+        Collector::new()
+    }
+}
+
+sync::thread_local! {
+    // Created on first use and dropped when the thread exits, which hands
+    // its garbage over to the orphan queue.
+    static WORKER: Worker = default_collector().register();
+}
+
+/// Pins the default worker of the calling thread. Library code can use it
+/// to protect loads without asking its callers for a `Worker`.
+pub fn pin() -> Guard {
+    with_worker(Worker::pin)
+}
+
+/// Blocks until a full grace period has passed on the default collector
+/// and then reclaims the garbage retired before the call. See
+/// `Worker::synchronize`.
+pub fn synchronize() {
+    with_worker(Worker::synchronize);
+}
+
+/// Starts a background reclaimer for the default collector. See
+/// `Collector::spawn_reclaimer`.
+#[cfg(not(loom))]
+#[must_use = "the reclaimer thread is stopped when the Reclaimer is dropped"]
+pub fn spawn_reclaimer(interval: Duration, batch: usize) -> Reclaimer {
+    default_collector().spawn_reclaimer(interval, batch)
+}
+
+/// Sets the thresholds of the default collector, which starts out trying to
+/// advance the epoch on every retirement. See `Collector::set_thresholds`.
+pub fn set_default_thresholds(retirements: usize, bytes: usize) {
+    default_collector().set_thresholds(retirements, bytes);
+}
+
+/// Takes a snapshot of the default collector. See `Collector::stats`.
+pub fn stats() -> Stats {
+    default_collector().stats()
 }
 
-// TODO: Add loom tests. Find a way to use the loom variant the thread local with
-// lazily initialized statics. The loom::thread_local macro does not match for a
-// macro call inside of it. If it were to be true we could have used lazy_static.
+/// Installs a stalled reader callback on the default collector. See
+/// `Collector::on_stall`.
+pub fn on_stall<F>(threshold: Duration, callback: F)
+where
+    F: Fn(&Stall) + Send + Sync + 'static,
+{
+    default_collector().on_stall(threshold, callback);
+}
+
+/// Runs `f` with the default worker of the calling thread, registering it
+/// with the default collector on first use. If the thread local has already
+/// been destroyed, a temporary worker is used instead.
+pub fn with_worker<F, R>(f: F) -> R
+where
+    F: FnOnce(&Worker) -> R,
+{
+    let mut f = Some(f);
+    WORKER
+        .try_with(|worker| (f.take().unwrap())(worker))
+        .unwrap_or_else(|_| (f.take().unwrap())(&default_collector().register()))
+}
 
-/// Holds the current state.
-struct Epoch {
+/// An independent reclamation domain. It holds its own epoch counter and
+/// registrations, and every registration holds its own garbage, so a slow
+/// reader only ever stalls the collector it is registered with.
+///
+/// Workers borrow their collector for the rest of the program, therefore
+/// collectors are meant to live in a `static`.
+pub struct Collector {
     counter: AtomicUsize,
     registrations: Registrations,
+    // Garbage left behind by workers that went away. The count lets the
+    // common case of an empty queue skip the lock.
+    orphans: Mutex<Vec<Orphan>>,
+    orphaned: AtomicUsize,
+    // Held for the whole of `Worker::synchronize`, so that a call can rely
+    // on the garbage taken by the calls before it being reclaimed already.
+    synchronizing: Mutex<()>,
+    batches: Batches,
+    // A worker only walks the registrations to advance the epoch, and only
+    // rotates its garbage lists, once it has retired this many entries or
+    // this many bytes since the last attempt.
+    retirements: AtomicUsize,
+    bytes: AtomicUsize,
+    // Number of running reclaimer threads. While there is one, workers hand
+    // their eligible garbage over to the orphan queue instead of running
+    // the destructors themselves.
+    reclaimers: AtomicUsize,
+    // Retirements of registrations that have been removed, the live ones
+    // keep their own count.
+    retired: AtomicUsize,
+    reclaimed: AtomicUsize,
+    advances: AtomicUsize,
+    failed_advances: AtomicUsize,
+    // When the epoch last moved, see `now`.
+    advanced_at: AtomicU64,
+    // Nanoseconds a reader may stay at a stale epoch before the stall
+    // callback is called, u64::MAX while no callback is installed.
+    stall_threshold: AtomicU64,
+    stall_callback: RwLock<Option<StallCallback>>,
+    // Poisoned values whose memory is only freed once the epoch is
+    // `QUARANTINE` past their stamp. The count lets loads skip the lock.
+    #[cfg(feature = "debug-reclaim")]
+    quarantine: Mutex<Vec<Quarantined>>,
+    #[cfg(feature = "debug-reclaim")]
+    quarantined: AtomicUsize,
+}
+
+/// The byte that reclaimed values are overwritten with when the
+/// `debug-reclaim` feature is enabled.
+#[cfg(feature = "debug-reclaim")]
+pub const POISON: u8 = 0xDE;
+
+/// Number of epochs the memory of a poisoned value stays allocated.
+#[cfg(feature = "debug-reclaim")]
+const QUARANTINE: usize = 4;
+
+/// A poisoned value waiting for its memory to be freed.
+#[cfg(feature = "debug-reclaim")]
+struct Quarantined {
+    ptr: NonNull<u8>,
+    layout: Layout,
+    stamp: usize,
+}
+
+// SAFETY:
+//    The value is gone, what is left is plain memory that may be freed
+//    by any thread.
+#[cfg(feature = "debug-reclaim")]
+unsafe impl Send for Quarantined {}
+
+type StallCallback = Box<dyn Fn(&Stall) + Send + Sync>;
+
+/// Nanoseconds since the first call, used for the timestamps which are
+/// shared between threads.
+fn now() -> u64 {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+/// Handed to the callback installed with `Collector::on_stall` when a
+/// registration keeps the epoch from advancing for too long.
+#[derive(Debug, Clone)]
+pub struct Stall {
+    /// The thread that acquired the stalled registration.
+    pub thread: ThreadId,
+    /// Its name, if it has one.
+    pub name: Option<String>,
+    /// The epoch the registration is pinned at.
+    pub pinned: usize,
+    /// The epoch the collector is at, which cannot move on.
+    pub epoch: usize,
+    /// How long the registration has been pinned at a stale epoch.
+    pub stalled_for: Duration,
+}
+
+/// A snapshot of a collector, see `Collector::stats`. The counters are read
+/// one after the other, so they are only approximate while workers run.
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    /// The global epoch.
+    pub epoch: usize,
+    /// Registrations held by a worker or one of its guards.
+    pub live_registrations: usize,
+    /// Registrations kept around for reuse.
+    pub free_registrations: usize,
+    /// Entries ever handed to the garbage lists, including closures.
+    pub retired: usize,
+    /// Entries whose grace period is over and which have been reclaimed.
+    pub reclaimed: usize,
+    /// Garbage handed over by dropped workers that still waits for its
+    /// grace period. Unlike the counters above, it includes the nodes of
+    /// removed registrations.
+    pub orphaned: usize,
+    /// Attempts to move the epoch forward that succeeded.
+    pub advances: usize,
+    /// Attempts to move the epoch forward that were blocked by a reader
+    /// or lost the race to another thread.
+    pub failed_advances: usize,
+    /// One entry per live registration.
+    pub threads: Vec<ThreadStats>,
+}
+
+/// What a single live registration holds on to.
+#[derive(Debug, Clone)]
+pub struct ThreadStats {
+    /// The thread that acquired the registration.
+    pub thread: ThreadId,
+    /// Its name, if it has one.
+    pub name: Option<String>,
+    /// The epoch the registration is pinned at, if it is pinned.
+    pub pinned: Option<usize>,
+    /// Entries in the recent garbage list.
+    pub recent: usize,
+    /// Entries in the previous garbage list.
+    pub previous: usize,
+}
+
+/// How many registrations without a worker are kept around for reuse.
+/// Any registration released beyond that is unlinked and freed.
+const FREE_REGISTRATIONS: usize = 4;
+
+// Registrations are aligned to at least 8 bytes, so the lowest bit of a
+// `next` pointer is free to mark its node as being removed.
+const MARK: usize = 1;
+
+fn unmarked(ptr: *mut Registration) -> *mut Registration {
+    ptr.map_addr(|addr| addr & !MARK)
+}
+
+impl Collector {
+    sync::const_fn! {
+        /// A collector whose workers try to advance the epoch on every
+        /// retirement.
+        pub fn new() -> Self {
+            Self::with_thresholds(1, usize::MAX)
+        }
+    }
+
+    sync::const_fn! {
+        /// A collector whose workers try to advance the epoch, and reclaim
+        /// what became eligible, only after `retirements` entries or `bytes`
+        /// bytes of garbage since their last attempt, whichever comes first.
+        /// Larger thresholds make retiring cheaper at the cost of keeping more
+        /// garbage around. Pass `usize::MAX` to disable either of them.
+        pub fn with_thresholds(retirements: usize, bytes: usize) -> Self {
+            Self {
+                counter: AtomicUsize::new(0),
+                registrations: Registrations::new(),
+                orphans: Mutex::new(Vec::new()),
+                orphaned: AtomicUsize::new(0),
+                synchronizing: Mutex::new(()),
+                batches: Batches::new(),
+                retirements: AtomicUsize::new(retirements),
+                bytes: AtomicUsize::new(bytes),
+                reclaimers: AtomicUsize::new(0),
+                retired: AtomicUsize::new(0),
+                reclaimed: AtomicUsize::new(0),
+                advances: AtomicUsize::new(0),
+                failed_advances: AtomicUsize::new(0),
+                advanced_at: AtomicU64::new(0),
+                stall_threshold: AtomicU64::new(u64::MAX),
+                stall_callback: RwLock::new(None),
+                #[cfg(feature = "debug-reclaim")]
+                quarantine: Mutex::new(Vec::new()),
+                #[cfg(feature = "debug-reclaim")]
+                quarantined: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    /// Replaces the thresholds given to `Collector::with_thresholds`. Every
+    /// worker picks them up with its next retirement.
+    pub fn set_thresholds(&self, retirements: usize, bytes: usize) {
+        self.retirements.store(retirements, Ordering::Relaxed);
+        self.bytes.store(bytes, Ordering::Relaxed);
+    }
+
+    /// Calls `callback` whenever an attempt to advance the epoch finds a
+    /// registration that has been pinned at a stale epoch for longer than
+    /// `threshold`, for example because a `Res` is held across a slow
+    /// syscall. Every stall is reported once. Detection piggybacks on the
+    /// attempts to advance, so a collector nobody retires into, and which
+    /// has no reclaimer thread, never reports anything. The callback runs
+    /// on the thread that found the stall and replaces any previous one.
+    pub fn on_stall<F>(&self, threshold: Duration, callback: F)
+    where
+        F: Fn(&Stall) + Send + Sync + 'static,
+    {
+        let mut installed = self.stall_callback.write().unwrap();
+        *installed = Some(Box::new(callback));
+        let nanos = u64::try_from(threshold.as_nanos()).unwrap_or(u64::MAX - 1);
+        self.stall_threshold.store(nanos, Ordering::Release);
+    }
+
+    /// Reports `reg` if it has kept the epoch from moving past `count` for
+    /// longer than the threshold. It has to be pinned at `count - 1`, so
+    /// it has been stale ever since the epoch moved to `count`.
+    fn check_stall(&self, reg: &Registration, pinned: isize, count: usize) {
+        let threshold = self.stall_threshold.load(Ordering::Acquire);
+        // The registration may also have pinned after the epoch moved on
+        // from what we read.
+        if threshold == u64::MAX || pinned >= count as isize {
+            return;
+        }
+        let stalled_for = now().saturating_sub(self.advanced_at.load(Ordering::Relaxed));
+        if stalled_for < threshold {
+            return;
+        }
+        let reported = reg.reported.load(Ordering::Relaxed);
+        if reported == pinned
+            || reg
+                .reported
+                .compare_exchange(reported, pinned, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+        {
+            return;
+        }
+        let owner = reg.thread.lock().unwrap().clone();
+        let stall = Stall {
+            thread: owner.id(),
+            name: owner.name().map(str::to_owned),
+            pinned: pinned as usize,
+            epoch: count,
+            stalled_for: Duration::from_nanos(stalled_for),
+        };
+        if let Some(callback) = &*self.stall_callback.read().unwrap() {
+            callback(&stall);
+        }
+    }
+
+    /// Takes a snapshot of the epoch, the registrations and the garbage
+    /// of the collector. Meant for finding out whether memory grows
+    /// because the epoch is stuck or because garbage is piling up.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            epoch: self.counter.load(Ordering::Acquire),
+            retired: self.retired.load(Ordering::Relaxed),
+            reclaimed: self.reclaimed.load(Ordering::Relaxed),
+            advances: self.advances.load(Ordering::Relaxed),
+            failed_advances: self.failed_advances.load(Ordering::Relaxed),
+            ..Stats::default()
+        };
+        // Same as in `Collector::find_register`.
+        self.registrations.walkers.fetch_add(1, Ordering::SeqCst);
+        let mut current = self.registrations.head.load(Ordering::Acquire);
+        while !current.is_null() {
+            // SAFETY:
+            //    Same as in `Collector::find_register`.
+            let reg = unsafe { &(*current) };
+            let next = reg.next.load(Ordering::Acquire);
+            stats.retired += reg.retired.load(Ordering::Relaxed);
+            if next.addr() & MARK != 0 {
+                // Being removed, its count is about to move over.
+            } else if reg.active.load(Ordering::Acquire) {
+                stats.free_registrations += 1;
+            } else {
+                stats.live_registrations += 1;
+                let pinned = reg.counter.load(Ordering::Relaxed);
+                let owner = reg.thread.lock().unwrap().clone();
+                stats.threads.push(ThreadStats {
+                    thread: owner.id(),
+                    name: owner.name().map(str::to_owned),
+                    pinned: (pinned >= 0).then_some(pinned as usize),
+                    recent: reg.recent_len.load(Ordering::Relaxed),
+                    previous: reg.previous_len.load(Ordering::Relaxed),
+                });
+            }
+            current = unmarked(next);
+        }
+        self.registrations.walkers.fetch_sub(1, Ordering::Release);
+        stats.orphaned = self.orphaned.load(Ordering::Relaxed);
+        stats
+    }
+
+    /// SAFETY:
+    ///    Every entry must have gone through a grace period.
+    unsafe fn run(&self, elements: Vec<ListEntry>) {
+        let counted = elements.iter().filter(|e| e.counted()).count();
+        self.reclaimed.fetch_add(counted, Ordering::Relaxed);
+        for element in elements {
+            #[cfg(feature = "debug-reclaim")]
+            let Some(element) = self.quarantine(element) else {
+                continue;
+            };
+            element.run();
+        }
+        #[cfg(feature = "debug-reclaim")]
+        self.release_quarantine();
+    }
+
+    /// Drops the value of a boxed entry, fills its memory with `POISON` and
+    /// keeps the memory allocated until the epoch is `QUARANTINE` further.
+    /// Entries the quarantine cannot take are handed back.
+    ///
+    /// SAFETY:
+    ///    Same as for `run`.
+    #[cfg(feature = "debug-reclaim")]
+    unsafe fn quarantine(&self, element: ListEntry) -> Option<ListEntry> {
+        let ListEntry::Pointer { value, deleter } = element else {
+            return Some(element);
+        };
+        if !deleter.boxed() {
+            return Some(element);
+        }
+        let layout = Layout::for_value(value.as_ref());
+        ptr::drop_in_place(value.as_ptr());
+        if layout.size() == 0 {
+            // A zero sized box owns no memory.
+            return None;
+        }
+        ptr::write_bytes(value.as_ptr() as *mut u8, POISON, layout.size());
+        let stamp = self.counter.load(Ordering::Acquire);
+        let mut quarantine = self.quarantine.lock().unwrap();
+        quarantine.push(Quarantined {
+            ptr: value.cast(),
+            layout,
+            stamp,
+        });
+        self.quarantined.fetch_add(1, Ordering::Release);
+        None
+    }
+
+    /// Frees the memory of the quarantined values that have waited long
+    /// enough.
+    #[cfg(feature = "debug-reclaim")]
+    fn release_quarantine(&self) {
+        let epoch = self.counter.load(Ordering::Acquire);
+        let expired: Vec<Quarantined> = {
+            let mut quarantine = self.quarantine.lock().unwrap();
+            let (expired, kept) = mem::take(&mut *quarantine)
+                .into_iter()
+                .partition(|q| epoch >= q.stamp + QUARANTINE);
+            *quarantine = kept;
+            expired
+        };
+        self.quarantined.fetch_sub(expired.len(), Ordering::Release);
+        for q in expired {
+            // SAFETY:
+            //    The memory came from a box of this layout and its value
+            //    has already been dropped.
+            unsafe { dealloc(q.ptr.as_ptr(), q.layout) };
+        }
+    }
+
+    /// Panics if `ptr` points to a value that has already been reclaimed,
+    /// which means it was loaded from a location it should have been
+    /// unlinked from before it was retired.
+    #[cfg(feature = "debug-reclaim")]
+    fn check_live<T>(&self, ptr: *mut T) {
+        if ptr.is_null() || self.quarantined.load(Ordering::Acquire) == 0 {
+            return;
+        }
+        let addr = ptr as *mut u8;
+        let quarantine = self.quarantine.lock().unwrap();
+        let start = |q: &Quarantined| q.ptr.as_ptr();
+        if quarantine
+            .iter()
+            .any(|q| start(q) <= addr && addr < start(q).wrapping_add(q.layout.size()))
+        {
+            panic!("loaded a pointer to a reclaimed value at {addr:p}");
+        }
+    }
+
+    /// Spawns a thread which advances the epoch and reclaims garbage every
+    /// `interval`, running at most `batch` destructors per round. While it
+    /// runs, workers no longer run destructors when they retire something
+    /// and hand them to the thread instead. Only `Worker::flush` and
+    /// `Worker::synchronize` still reclaim on the calling thread. The
+    /// thread is stopped when the returned `Reclaimer` is dropped.
+    #[cfg(not(loom))]
+    #[must_use = "the reclaimer thread is stopped when the Reclaimer is dropped"]
+    pub fn spawn_reclaimer(&'static self, interval: Duration, batch: usize) -> Reclaimer {
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&stop);
+        self.reclaimers.fetch_add(1, Ordering::SeqCst);
+        let handle = thread::Builder::new()
+            .name("epoch-reclaimer".into())
+            .spawn(move || {
+                let worker = self.register();
+                while !flag.load(Ordering::Acquire) {
+                    // Walking the registrations is only safe while pinned.
+                    let guard = worker.pin();
+                    self.try_advance();
+                    mem::drop(guard);
+                    self.collect_orphans(batch);
+                    thread::park_timeout(interval);
+                }
+            })
+            .expect("failed to spawn the reclaimer thread");
+        Reclaimer {
+            collector: self,
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// Reuses a registration which is no longer owned by any worker.
+    pub fn find_register(&'static self) -> Option<Worker> {
+        // We are not pinned, so the walk is announced separately and the
+        // epoch does not advance until it is over. Pairs with the fence in
+        // `Collector::try_advance`.
+        self.registrations.walkers.fetch_add(1, Ordering::SeqCst);
+        let mut ret = None;
+        let mut current = self.registrations.head.load(Ordering::Acquire);
+        while !current.is_null() {
+            // SAFETY:
+            //    A registration that is unlinked is only freed after the
+            //    epoch has advanced twice, which cannot happen while we
+            //    are walking. Therefore, the operation is safe.
+            let deref = unsafe { &(*current) };
+            if deref
+                .active
+                .compare_exchange(true, false, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+            {
+                self.registrations.free.fetch_sub(1, Ordering::Relaxed);
+                deref.counter.store(-1, Ordering::Relaxed);
+                deref.reported.store(-1, Ordering::Relaxed);
+                deref.guards.set(0);
+                deref.pending.set((0, 0));
+                deref.worker.set(true);
+                *deref.thread.lock().unwrap() = thread::current();
+                // The pointer that came out of the Box is kept rather than
+                // one made from `deref`, since the node is eventually freed
+                // through it.
+                // SAFETY:
+                //    `current` was checked to be non-null above.
+                let reg = unsafe { NonNull::new_unchecked(current) };
+                ret = Some(Worker { reg });
+                break;
+            } else {
+                current = unmarked(deref.next.load(Ordering::Acquire));
+            }
+        }
+        self.registrations.walkers.fetch_sub(1, Ordering::Release);
+        ret
+    }
+
+    pub fn create_register(&'static self) -> Worker {
+        // The head we link behind must not be freed and replaced by a new
+        // node at the same address before our CAS, or we would link behind
+        // a dangling pointer. Same as in `Collector::find_register`.
+        self.registrations.walkers.fetch_add(1, Ordering::SeqCst);
+        let reg = loop {
+            let current = self.registrations.head.load(Ordering::Acquire);
+            let new = Registration {
+                counter: AtomicIsize::new(-1),
+                guards: Cell::new(0),
+                pending: Cell::new((0, 0)),
+                retired: AtomicUsize::new(0),
+                recent_len: AtomicUsize::new(0),
+                previous_len: AtomicUsize::new(0),
+                thread: Mutex::new(thread::current()),
+                reported: AtomicIsize::new(-1),
+                worker: Cell::new(true),
+                garbage: Mutex::new(Garbage::new()),
+                next: AtomicPtr::new(current),
+                active: AtomicBool::new(false),
+                collector: self,
+            };
+            let boxed = Box::into_raw(Box::new(new));
+            if self
+                .registrations
+                .head
+                .compare_exchange(current, boxed, Ordering::Release, Ordering::Relaxed)
+                .is_ok()
+            {
+                // SAFETY:
+                //    The pointer comes from a Box, so it cannot be null.
+                //    Therefore the operation is safe.
+                break unsafe { NonNull::new_unchecked(boxed) };
+            } else {
+                // SAFETY:
+                //    As the function makes it clear, the underlying
+                //    raw pointer can never be null and the function is
+                //    called only once on a pointer. Therefore,
+                //    the operation is safe.
+                let _ = unsafe { Box::from_raw(boxed) };
+            }
+        };
+        self.registrations.walkers.fetch_sub(1, Ordering::Release);
+        Worker { reg }
+    }
+
+    /// Reuses a free registration if there is one, otherwise creates one.
+    pub fn register(&'static self) -> Worker {
+        self.find_register()
+            .unwrap_or_else(|| self.create_register())
+    }
+
+    /// Hands garbage which no worker is going to look after anymore to
+    /// the collector.
+    fn push_orphan(&self, orphan: Orphan) {
+        let len = orphan.elements.len();
+        self.orphans.lock().unwrap().push(orphan);
+        self.orphaned.fetch_add(len, Ordering::Relaxed);
+    }
+
+    /// Reclaims up to `budget` entries of the orphans whose grace period
+    /// is over. They are taken out under the lock and the destructors run
+    /// once it is released.
+    fn collect_orphans(&self, mut budget: usize) {
+        if self.orphaned.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+        let counter = self.counter.load(Ordering::Acquire);
+        let mut rec = Vec::new();
+        let batch = {
+            let mut orphans = self.orphans.lock().unwrap();
+            for orphan in orphans.iter_mut() {
+                if budget > 0 && counter >= orphan.stamp + 2 {
+                    let take = budget.min(orphan.elements.len());
+                    budget -= take;
+                    rec.extend(orphan.elements.drain(..take));
+                }
+            }
+            orphans.retain(|orphan| !orphan.elements.is_empty());
+            self.orphaned.fetch_sub(rec.len(), Ordering::Relaxed);
+            (!rec.is_empty()).then(|| self.batches.begin())
+        };
+        //SAFETY:
+        //   Same as in Worker::rearrange, the entries were
+        //   checked when they were retired.
+        unsafe { self.run(rec) };
+        mem::drop(batch);
+    }
+
+    /// Takes the garbage of every registration along with the whole orphan
+    /// queue, see `Worker::synchronize`.
+    fn take_all(&self) -> Vec<ListEntry> {
+        // Same as in `Collector::find_register`.
+        self.registrations.walkers.fetch_add(1, Ordering::SeqCst);
+        let mut elements = Vec::new();
+        let mut current = self.registrations.head.load(Ordering::Acquire);
+        while !current.is_null() {
+            // SAFETY:
+            //    Same as in `Collector::find_register`.
+            let reg = unsafe { &(*current) };
+            elements.append(&mut reg.take_garbage());
+            current = unmarked(reg.next.load(Ordering::Acquire));
+        }
+        self.registrations.walkers.fetch_sub(1, Ordering::Release);
+        let orphans = mem::take(&mut *self.orphans.lock().unwrap());
+        for mut orphan in orphans {
+            self.orphaned
+                .fetch_sub(orphan.elements.len(), Ordering::Relaxed);
+            elements.append(&mut orphan.elements);
+        }
+        elements
+    }
+
+    /// Moves the epoch one step forward if every pinned registration has
+    /// announced the current one. Returns the epoch that was observed
+    /// after the attempt.
+    fn try_advance(&self) -> usize {
+        let count = self.counter.load(Ordering::Relaxed);
+        // Pairs with the fence in `Worker::pin`. Either we see the
+        // announcement of a thread that pinned, or that thread sees
+        // everything which was unlinked before this point.
+        atomic::fence(Ordering::SeqCst);
+        // Unpinned walkers keep every registration alive, see
+        // `Collector::find_register`.
+        if self.registrations.walkers.load(Ordering::Relaxed) != 0 {
+            self.failed_advances.fetch_add(1, Ordering::Relaxed);
+            return count;
+        }
+        // With stall detection on, the walk goes on past the first blocker
+        // so that every stalled reader gets reported, not just the first.
+        let watch = self.stall_threshold.load(Ordering::Relaxed) != u64::MAX;
+        let mut blocked = false;
+        let mut current = self.registrations.head.load(Ordering::Acquire);
+        while !current.is_null() {
+            // SAFETY:
+            //    The operation is safe because we check the
+            //    nullability of current before dereferencing
+            //    and the the responsibility of giving a safe pointer
+            //    in this case does not rest on the user but is a part
+            //    of the implementation itself and I make sure that those
+            //    safety invariants are upheld. Only pinned threads call
+            //    this, so nodes unlinked under us are not freed yet.
+            let reg = unsafe { &(*current) };
+            let reg_counter = reg.counter.load(Ordering::Relaxed);
+            if reg_counter >= 0 && reg_counter != count as isize {
+                if !blocked {
+                    blocked = true;
+                    self.failed_advances.fetch_add(1, Ordering::Relaxed);
+                }
+                self.check_stall(reg, reg_counter, count);
+                if !watch {
+                    return count;
+                }
+            }
+            current = unmarked(reg.next.load(Ordering::Acquire));
+        }
+        if blocked {
+            return count;
+        }
+        // Whatever the readers did before unpinning has to happen before
+        // anything that gets reclaimed because of this advance.
+        atomic::fence(Ordering::Acquire);
+        let ret = count + 1;
+        match self
+            .counter
+            .compare_exchange(count, ret, Ordering::Release, Ordering::Relaxed)
+        {
+            Ok(_) => {
+                self.advances.fetch_add(1, Ordering::Relaxed);
+                self.advanced_at.store(now(), Ordering::Relaxed);
+                ret
+            }
+            Err(found) => {
+                self.failed_advances.fetch_add(1, Ordering::Relaxed);
+                found
+            }
+        }
+    }
+}
+
+impl Default for Collector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Handle to a reclaimer thread started with `Collector::spawn_reclaimer`.
+/// Dropping it stops the thread and waits for it to exit, after which
+/// workers go back to reclaiming their own garbage.
+#[cfg(not(loom))]
+#[must_use = "the reclaimer thread is stopped when the Reclaimer is dropped"]
+pub struct Reclaimer {
+    collector: &'static Collector,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+#[cfg(not(loom))]
+impl Reclaimer {
+    /// Wakes the thread up for a round without waiting for the interval.
+    pub fn wake(&self) {
+        if let Some(handle) = &self.handle {
+            handle.thread().unpark();
+        }
+    }
+
+    /// Stops the thread and waits for it to exit. Same as dropping.
+    pub fn stop(self) {}
+}
+
+#[cfg(not(loom))]
+impl Drop for Reclaimer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            // A panicking destructor already took the thread down, there is
+            // nothing left to stop.
+            let _ = handle.join();
+        }
+        self.collector.reclaimers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Counts the batches of garbage that were taken out of the lists or the
+/// orphan queue and are being reclaimed. `Worker::synchronize` has to wait
+/// for the batches taken before it looked, but not for later ones, or a
+/// busy collector could keep it waiting forever. So batches count
+/// themselves in the slot of the current phase, and waiting flips the
+/// phase and lets the old slot drain.
+struct Batches {
+    phase: AtomicUsize,
+    running: [AtomicUsize; 2],
+}
+
+impl Batches {
+    sync::const_fn! {
+        fn new() -> Self {
+            Self {
+                phase: AtomicUsize::new(0),
+                running: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            }
+        }
+    }
+
+    /// Counts a batch until the returned value is dropped. Has to be called
+    /// before the entries are taken out, while holding the lock they are
+    /// taken from.
+    fn begin(&self) -> Batch<'_> {
+        loop {
+            let phase = self.phase.load(Ordering::SeqCst);
+            let running = &self.running[phase & 1];
+            running.fetch_add(1, Ordering::SeqCst);
+            // A waiter that flipped the phase in between may already have
+            // looked at the slot, so count in the new one instead.
+            if self.phase.load(Ordering::SeqCst) == phase {
+                return Batch { running };
+            }
+            running.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Waits until every batch that began before the call has ended. Only
+    /// one thread may wait at a time.
+    fn wait(&self) {
+        let phase = self.phase.fetch_add(1, Ordering::SeqCst);
+        let running = &self.running[phase & 1];
+        let mut round = 0;
+        while running.load(Ordering::Acquire) != 0 {
+            sync::backoff(round);
+            round += 1;
+        }
+    }
+}
+
+/// A batch counted by `Batches::begin`.
+struct Batch<'a> {
+    running: &'a AtomicUsize,
+}
+
+impl Drop for Batch<'_> {
+    fn drop(&mut self) {
+        // The destructors of the batch happen before the wait is over.
+        self.running.fetch_sub(1, Ordering::Release);
+    }
 }
 
-impl Epoch {
+/// The two garbage lists of a registration, see `Registration::garbage`.
+struct Garbage {
+    recent: List,
+    previous: List,
+}
+
+impl Garbage {
     const fn new() -> Self {
         Self {
-            counter: AtomicUsize::new(0),
-            registrations: Registrations::new(),
+            recent: List::new(),
+            previous: List::new(),
         }
     }
 }
@@ -53,15 +862,37 @@ impl List {
     }
 }
 
-struct ListEntry {
-    value: NonNull<dyn Common>,
-    deleter: &'static dyn Reclaim,
+/// Garbage left behind by a worker that was dropped, or handed to the
+/// reclaimer thread. It is stamped with the epoch at the time it was
+/// handed over, which is never older than any of its entries, and whoever
+/// finds the epoch two steps ahead of the stamp reclaims it.
+struct Orphan {
+    stamp: usize,
+    elements: Vec<ListEntry>,
+}
+
+// SAFETY:
+//    Only `Send` values and closures can be retired and every deleter is
+//    `Sync`, so the entries can be reclaimed by any thread. Registration
+//    nodes are unlinked and no longer used by anyone once they are queued.
+unsafe impl Send for Orphan {}
+
+/// Something that has to wait for a grace period. Either a pointer
+/// to reclaim or an arbitrary closure to run, or the node of a removed
+/// registration, which is left out of `Stats`.
+enum ListEntry {
+    Pointer {
+        value: NonNull<dyn Common>,
+        deleter: &'static dyn Reclaim,
+    },
+    Closure(Box<dyn FnOnce() + Send>),
+    Registration(NonNull<Registration>),
 }
 
 impl ListEntry {
     fn new(value: *mut dyn Common, deleter: &'static dyn Reclaim) -> Option<ListEntry> {
         if let Some(ptr) = NonNull::new(value) {
-            let ret = ListEntry {
+            let ret = ListEntry::Pointer {
                 value: ptr,
                 deleter,
             };
@@ -70,6 +901,33 @@ impl ListEntry {
             None
         }
     }
+
+    /// Rough number of bytes kept alive by the entry.
+    fn size(&self) -> usize {
+        match self {
+            // SAFETY:
+            //    A retired pointer stays valid until it is reclaimed.
+            ListEntry::Pointer { value, .. } => unsafe { mem::size_of_val(value.as_ref()) },
+            ListEntry::Closure(f) => mem::size_of_val(&**f),
+            ListEntry::Registration(_) => mem::size_of::<Registration>(),
+        }
+    }
+
+    /// SAFETY:
+    ///    For pointer entries the requirements of the
+    ///    deleter the pointer was retired with must hold.
+    unsafe fn run(self) {
+        match self {
+            ListEntry::Pointer { value, deleter } => deleter.reclaim(value.as_ptr()),
+            ListEntry::Closure(f) => f(),
+            ListEntry::Registration(reg) => mem::drop(Box::from_raw(reg.as_ptr())),
+        }
+    }
+
+    /// Whether the entry was retired by a user, as opposed to the library.
+    fn counted(&self) -> bool {
+        !matches!(self, ListEntry::Registration(_))
+    }
 }
 
 /// This trait is necessary to create a common characteristic for every
@@ -81,12 +939,22 @@ pub trait Common {}
 impl<T> Common for T {}
 
 /// A trait to make sure that the pointers are dropped in accordance with
-/// how they were constructed in the first place.
-pub trait Reclaim {
-    ///SAFETY:
-    ///    Safety relies on the promise that 'ptr' should not be null
-    ///    and it meets all the requirements of being a valid pointer.
+/// how they were constructed in the first place. Garbage may be reclaimed
+/// by another thread than the one that retired it, hence the `Sync` bound.
+pub trait Reclaim: Sync {
+    /// # Safety
+    ///
+    /// Safety relies on the promise that 'ptr' should not be null
+    /// and it meets all the requirements of being a valid pointer.
     unsafe fn reclaim(&self, ptr: *mut dyn Common);
+
+    /// Whether `reclaim` drops the value and hands its memory back to the
+    /// global allocator, the way dropping a `Box` does. Only such values
+    /// are quarantined by the `debug-reclaim` feature, the rest are
+    /// reclaimed right away.
+    fn boxed(&self) -> bool {
+        false
+    }
 }
 
 /// A type for reclaiming memory pointed to by raw pointers that
@@ -105,6 +973,10 @@ impl Default for DropBox {
     }
 }
 
+/// The deleter for everything the crate boxes on its own, the values of an
+/// `Atomic` and the nodes of the collections.
+pub(crate) static DROPBOX: DropBox = DropBox::new();
+
 impl Reclaim for DropBox {
     /// SAFETY:
     ///     All the pointer safety requirements such as
@@ -116,6 +988,10 @@ impl Reclaim for DropBox {
         let owned = Box::from_raw(ptr);
         mem::drop(owned);
     }
+
+    fn boxed(&self) -> bool {
+        true
+    }
 }
 
 /// A type for reclaiming memory pointed to by raw pointers that were
@@ -147,83 +1023,305 @@ impl Reclaim for DropPointer {
 }
 
 /// List of all the registrations.
-/// None of the registrations will be dropped until
-/// the end of the program.
-/// Therefore the ABA problem cannot arise.
+/// New registrations are only ever pushed at the head. A registration
+/// is removed by marking its own `next` pointer first, after which no
+/// CAS can link anything behind it, and then unlinking it from its
+/// predecessor. Unlinked registrations are retired like any other garbage,
+/// so a node cannot be freed and reused while someone still walks over it
+/// and the ABA problem cannot arise.
 struct Registrations {
     head: AtomicPtr<Registration>,
+    // Number of registrations without a worker that can be reused.
+    free: AtomicUsize,
+    // Number of walks in progress by threads that are not pinned.
+    walkers: AtomicUsize,
 }
 
 impl Registrations {
-    const fn new() -> Self {
-        Self {
-            head: AtomicPtr::new(ptr::null_mut()),
+    sync::const_fn! {
+        fn new() -> Self {
+            Self {
+                head: AtomicPtr::new(ptr::null_mut()),
+                free: AtomicUsize::new(0),
+                walkers: AtomicUsize::new(0),
+            }
         }
     }
 }
 
 /// Every thread registers itself before it does any operation.
 pub struct Registration {
-    counter: Cell<isize>,
+    // The epoch announced by the owner while it is pinned, -1 otherwise.
+    // The owner writes it and everyone walking the registrations reads it.
+    counter: AtomicIsize,
+    // Number of live guards. Only the first pin announces the epoch
+    // and only the last unpin clears it.
+    guards: Cell<usize>,
+    // Entries and bytes retired since the owner last tried to advance
+    // the epoch, see `Collector::with_thresholds`.
+    pending: Cell<(usize, usize)>,
+    // Written by the owner only, so that `Collector::stats` can see how
+    // much the registration holds without touching its lists.
+    retired: AtomicUsize,
+    recent_len: AtomicUsize,
+    previous_len: AtomicUsize,
+    // The thread that acquired the registration and the last stale epoch
+    // it was reported at, see `Collector::on_stall`.
+    thread: Mutex<Thread>,
+    reported: AtomicIsize,
+    // Whether a Worker still holds the registration. It is given back
+    // once both the worker and the last of its guards are gone.
+    worker: Cell<bool>,
+    // Every registration has got two lists. It starts pushing the things
+    // into the recent list, whose stamp is the global epoch read after the
+    // last push. When a push finds that the epoch has moved past that stamp
+    // it deallocates the memory pointed to by the pointers in the previous
+    // list, makes recent the previous, and recent starts over with a new
+    // Vec. As previous is stamped strictly before recent, which is stamped
+    // strictly before the current epoch, the previous list is always at
+    // least two epochs old when it is reclaimed. Apart from the worker
+    // owning the registration only `Worker::synchronize` touches them, to
+    // take them away, which is what the lock is for.
+    garbage: Mutex<Garbage>,
     next: AtomicPtr<Registration>,
     active: AtomicBool,
+    collector: &'static Collector,
 }
 
 impl Registration {
+    /// Same as `Collector::find_register` on the default collector.
     pub fn find_register() -> Option<Worker> {
-        let mut current = EPOCH.registrations.head.load(Ordering::Acquire);
-        while !current.is_null() {
-            // SAFETY:
-            //    The raw pointer cannot be null as a registration is
-            //    not deallocated until the end of the program.
-            //    Therefore, the operation is safe.
-            let deref = unsafe { &(*current) };
-            if deref
-                .active
-                .compare_exchange(true, false, Ordering::Relaxed, Ordering::Relaxed)
-                .is_ok()
-            {
-                deref.counter.set(-1);
-                let ret = Worker { reg: deref };
-                return Some(ret);
+        default_collector().find_register()
+    }
+
+    /// Same as `Collector::create_register` on the default collector.
+    pub fn create_register() -> Worker {
+        default_collector().create_register()
+    }
+
+    /// Takes the pointer the worker holds, so that the guard can free
+    /// the registration through it once both of them are gone.
+    fn pin(this: NonNull<Registration>) -> Guard {
+        // SAFETY:
+        //    Only called through a live worker.
+        let reg = unsafe { this.as_ref() };
+        let guards = reg.guards.get();
+        if guards == 0 {
+            let count = reg.collector.counter.load(Ordering::Relaxed);
+            reg.counter.store(count as isize, Ordering::Relaxed);
+            // The announcement has to be visible to everyone advancing the
+            // epoch before any pointer is loaded inside of the critical
+            // section. Pairs with the fences in `Collector::try_advance`
+            // and `Guard::defer_entry`. Advancing is left to retirements,
+            // so a pin never walks the registrations.
+            atomic::fence(Ordering::SeqCst);
+        }
+        reg.guards.set(guards + 1);
+        Guard { reg: this }
+    }
+
+    /// SAFETY:
+    ///    `this` must point to a registration held by a guard
+    ///    that is being dropped. It may be freed once this returns.
+    unsafe fn unpin(this: NonNull<Registration>) {
+        let reg = this.as_ref();
+        let guards = reg.guards.get() - 1;
+        reg.guards.set(guards);
+        if guards == 0 {
+            if reg.worker.get() {
+                // Everything read inside of the critical section happens
+                // before the epoch can be advanced past it.
+                reg.counter.store(-1, Ordering::Release);
             } else {
-                current = deref.next.load(Ordering::Acquire);
+                Registration::release(this);
             }
         }
-        None
     }
 
-    pub fn create_register() -> Worker {
-        loop {
-            let current = EPOCH.registrations.head.load(Ordering::Acquire);
-            let new = Registration {
-                counter: Cell::new(-1),
-                next: AtomicPtr::new(current),
-                active: AtomicBool::new(false),
-            };
-            let boxed = Box::into_raw(Box::new(new));
-            if EPOCH
-                .registrations
-                .head
-                .compare_exchange(current, boxed, Ordering::Release, Ordering::Relaxed)
+    /// Reclaims every list which is at least two epochs old, without
+    /// waiting for the next push to rotate them.
+    fn collect(&self) {
+        let epoch = self.collector.counter.load(Ordering::Acquire) as isize;
+        let mut rec = Vec::new();
+        let batch = {
+            let mut garbage = self.garbage.lock().unwrap();
+            if epoch >= garbage.previous.stamp + 2 {
+                rec = mem::take(&mut garbage.previous.elements);
+            }
+            if epoch >= garbage.recent.stamp + 2 {
+                rec.append(&mut garbage.recent.elements);
+            }
+            self.publish(&garbage);
+            (!rec.is_empty()).then(|| self.collector.batches.begin())
+        };
+        //SAFETY:
+        //   Same as in `Registration::rearrange`.
+        unsafe { self.collector.run(rec) };
+        mem::drop(batch);
+        self.collector.collect_orphans(usize::MAX);
+    }
+
+    fn rearrange(
+        &self,
+        mut garbage: MutexGuard<'_, Garbage>,
+        epoch: isize,
+        entry: Option<ListEntry>,
+    ) {
+        let recent = List {
+            stamp: epoch,
+            elements: entry.into_iter().collect(),
+        };
+        let recent = mem::replace(&mut garbage.recent, recent);
+        let List {
+            stamp: rec_stamp,
+            elements: rec,
+        } = mem::replace(&mut garbage.previous, recent);
+        self.publish(&garbage);
+        if self.collector.reclaimers.load(Ordering::Relaxed) != 0 {
+            // The reclaimer thread runs the destructors. An empty list may
+            // still carry the initial stamp, but a non-empty one never does.
+            // The lock is held until the orphan is queued, so that
+            // `Worker::synchronize` finds the garbage in one place or the
+            // other.
+            if !rec.is_empty() {
+                let orphan = Orphan {
+                    stamp: rec_stamp as usize,
+                    elements: rec,
+                };
+                self.collector.push_orphan(orphan);
+            }
+            return;
+        }
+        let batch = (!rec.is_empty()).then(|| self.collector.batches.begin());
+        mem::drop(garbage);
+        //SAFETY:
+        //   Safe because the ptr is checked to be non-null
+        //   before insertion and the fact that the user
+        //   is required to uphold the safety requirements
+        //   of a ptr i.e it must be valid. The list is at least
+        //   two epochs old, so nobody pinned can still hold them.
+        unsafe { self.collector.run(rec) };
+        mem::drop(batch);
+        self.collector.collect_orphans(usize::MAX);
+    }
+
+    /// Takes everything that is still waiting for a grace period.
+    fn take_garbage(&self) -> Vec<ListEntry> {
+        let mut garbage = self.garbage.lock().unwrap();
+        let Garbage { recent, previous } = mem::replace(&mut *garbage, Garbage::new());
+        self.publish(&garbage);
+        let mut elements = previous.elements;
+        elements.extend(recent.elements);
+        elements
+    }
+
+    /// Hands everything that is still waiting for a grace period over to
+    /// the orphan queue. Same as in `Registration::rearrange`, the lock is
+    /// held until the orphan is queued.
+    fn hand_over(&self) {
+        let mut garbage = self.garbage.lock().unwrap();
+        let stamp = self.collector.counter.load(Ordering::Acquire);
+        let Garbage { recent, previous } = mem::replace(&mut *garbage, Garbage::new());
+        self.publish(&garbage);
+        let mut elements = previous.elements;
+        elements.extend(recent.elements);
+        if !elements.is_empty() {
+            let orphan = Orphan { stamp, elements };
+            self.collector.push_orphan(orphan);
+        }
+    }
+
+    /// Makes the length of the lists visible to `Collector::stats`.
+    fn publish(&self, garbage: &Garbage) {
+        self.recent_len
+            .store(garbage.recent.elements.len(), Ordering::Relaxed);
+        self.previous_len
+            .store(garbage.previous.elements.len(), Ordering::Relaxed);
+    }
+
+    /// Gives the registration back once neither a worker nor a guard
+    /// uses it anymore. It is either kept for reuse or removed.
+    ///
+    /// SAFETY:
+    ///    `this` must point to a registration which has no worker and
+    ///    no guards left. It may be freed once this returns.
+    unsafe fn release(this: NonNull<Registration>) {
+        let reg = this.as_ref();
+        let collector = reg.collector;
+        if collector.registrations.free.load(Ordering::Relaxed) >= FREE_REGISTRATIONS {
+            Registration::remove(this);
+            return;
+        }
+        // Whatever is still waiting for a grace period is handed over to
+        // the collector, so that it does not depend on the registration
+        // being picked up again.
+        reg.hand_over();
+        reg.counter.store(-1, Ordering::Release);
+        collector.registrations.free.fetch_add(1, Ordering::Relaxed);
+        reg.active.store(true, Ordering::Release);
+    }
+
+    /// Unlinks the registration and retires it along with its garbage.
+    ///
+    /// SAFETY:
+    ///    Same as `Registration::release`.
+    unsafe fn remove(this: NonNull<Registration>) {
+        let reg = this.as_ref();
+        let collector = reg.collector;
+        let raw = this.as_ptr();
+        // Handed over while the node is still linked, so that
+        // `Worker::synchronize` finds it on the node or in the queue.
+        reg.hand_over();
+        // Announce an epoch on our own registration, which is still linked,
+        // so that the nodes we walk over below cannot be freed under us.
+        let count = collector.counter.load(Ordering::Relaxed);
+        reg.counter.store(count as isize, Ordering::Relaxed);
+        atomic::fence(Ordering::SeqCst);
+
+        // Nothing can be linked behind a marked node anymore, since every
+        // CAS on its `next` expects an unmarked pointer.
+        let _ = reg
+            .next
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |next| {
+                Some(next.map_addr(|addr| addr | MARK))
+            });
+        'retry: loop {
+            let mut pred = &collector.registrations.head;
+            let mut current = pred.load(Ordering::Acquire);
+            while current != raw {
+                // SAFETY:
+                //    We are pinned, and our own node is reachable from
+                //    here until we unlink it, so the list cannot end
+                //    before we find it.
+                let node = &*current;
+                pred = &node.next;
+                current = unmarked(pred.load(Ordering::Acquire));
+            }
+            let next = unmarked(reg.next.load(Ordering::Acquire));
+            // Fails if the predecessor is being removed as well, in which
+            // case it gets unlinked first and we try again.
+            if pred
+                .compare_exchange(raw, next, Ordering::AcqRel, Ordering::Acquire)
                 .is_ok()
             {
-                // SAFETY:
-                //    The pointer being dereferenced cannot be null
-                //    as a registration is never deallocated until the
-                //    end of the program. Therefore the operation is safe.
-                let shared = unsafe { &(*boxed) };
-                let ret = Worker { reg: shared };
-                return ret;
-            } else {
-                // SAFETY:
-                //    As the function makes it clear, the underlying
-                //    raw pointer can never be null and the function is
-                //    called only once on a pointer. Therefore,
-                //    the operation is safe.
-                let _ = unsafe { Box::from_raw(boxed) };
+                break 'retry;
             }
         }
+
+        let retired = reg.retired.load(Ordering::Relaxed);
+        collector.retired.fetch_add(retired, Ordering::Relaxed);
+        // We are done walking, and the node is unlinked, so advancing the
+        // epoch no longer looks at the announcement. It has to be cleared
+        // here, as the node may be freed as soon as the orphan is pushed.
+        reg.counter.store(-1, Ordering::Release);
+        // Same as in `Guard::defer_entry`, the node is unlinked by now.
+        atomic::fence(Ordering::SeqCst);
+        let stamp = collector.counter.load(Ordering::Acquire);
+        let orphan = Orphan {
+            stamp,
+            elements: vec![ListEntry::Registration(this)],
+        };
+        collector.push_orphan(orphan);
     }
 }
 
@@ -232,52 +1330,112 @@ impl Registration {
 /// to an inactive state in case of loads and the implementation of swap
 /// does it in the method call itself.
 pub struct Worker {
-    reg: &'static Registration,
+    reg: NonNull<Registration>,
 }
 
 impl Drop for Worker {
     fn drop(&mut self) {
-        self.reg.active.store(true, Ordering::Relaxed);
+        self.reg().worker.set(false);
+        if self.reg().guards.get() == 0 {
+            // SAFETY:
+            //    Neither the worker nor a guard holds the registration
+            //    anymore.
+            unsafe { Registration::release(self.reg) };
+        }
     }
 }
 
 /// A type which when dropped signals that the thread is no
 /// longer in a critcal section.
 pub struct Res<'a, T> {
-    worker: &'a Worker,
+    _guard: Guard,
     ptr: *mut T,
+    _marker: PhantomData<&'a Worker>,
 }
 
 impl<T> Res<'_, T> {
+    /// The loaded pointer without its tag. It must not be used once the
+    /// `Res` is dropped, the `debug-reclaim` feature poisons reclaimed
+    /// values to catch that.
     pub fn get_ptr(&self) -> *mut T {
-        self.ptr
+        decompose(self.ptr).0
+    }
+
+    /// The tag stored in the low bits of the loaded pointer.
+    pub fn tag(&self) -> usize {
+        decompose(self.ptr).1
     }
+
+    /// The loaded value, or `None` for a null pointer. It is borrowed from
+    /// the `Res`, so it cannot outlive the pin that protects it.
+    ///
+    /// # Safety
+    ///
+    /// `Worker::load` accepts any `AtomicPtr`, so nothing ties the pointer
+    /// to a live value. It must either be null or point to a valid `T`
+    /// which is only ever freed by retiring it through the collector of
+    /// this worker. `Atomic::get` reads without such promises.
+    pub unsafe fn as_ref(&self) -> Option<&T>
+    where
+        T: Sync,
+    {
+        // SAFETY:
+        //    Upheld by the caller. A retired value is not reclaimed while
+        //    the guard is alive.
+        unsafe { self.get_ptr().as_ref() }
+    }
+}
+
+/// A critical section obtained from `Worker::pin` or `epoch::pin`. As long
+/// as it is alive nothing loaded through it is reclaimed, so any number of
+/// loads, swaps and retirements can share the cost of a single pin. It
+/// keeps its registration alive even if the worker is dropped first.
+pub struct Guard {
+    reg: NonNull<Registration>,
 }
 
-impl<T> Drop for Res<'_, T> {
+impl Drop for Guard {
     fn drop(&mut self) {
-        self.worker.reg.counter.set(-1);
+        // SAFETY:
+        //    The guard is going away and the registration is
+        //    not touched through it anymore.
+        unsafe { Registration::unpin(self.reg) };
     }
 }
 
-impl Worker {
-    pub fn load<'a, T>(&'a self, ptr: &AtomicPtr<T>) -> Res<'a, T> {
-        let count = Self::try_advance();
-        self.reg.counter.set(count as isize);
-        let pointer = ptr.load(Ordering::Acquire);
-        Res {
-            worker: self,
-            ptr: pointer,
-        }
+impl Guard {
+    fn reg(&self) -> &Registration {
+        // SAFETY:
+        //    A registration is not released while a guard holds it.
+        unsafe { self.reg.as_ref() }
     }
 
-    /// The deleter parameter signifies a way the pointer that is going to be dropped.
-    /// Currently this will work as expected if the user is sure that the CAS will succeed
-    /// in the first attempt. If not so, the user must ensure that all the pointers are
-    /// constructed using a common method that is either a box or directly.
-    pub fn swap<T>(&self, ptr: &AtomicPtr<T>, new: T, deleter: &'static dyn Reclaim) {
-        let count = Self::try_advance();
-        self.reg.counter.set(count as isize);
+    /// Whether the guard belongs to the default collector, which is the
+    /// one the collections retire their nodes through.
+    pub(crate) fn is_default(&self) -> bool {
+        ptr::eq(self.reg().collector, default_collector())
+    }
+
+    pub fn load<T>(&self, ptr: &AtomicPtr<T>) -> Shared<'_, T> {
+        let loaded = ptr.load(Ordering::Acquire);
+        #[cfg(feature = "debug-reclaim")]
+        self.reg().collector.check_live(loaded);
+        Shared::from_raw(loaded)
+    }
+
+    /// Stores `new` and retires the replaced pointer with `deleter`. Same as
+    /// `Worker::swap` except that it does not pin on its own. Prefer
+    /// `Atomic::swap`, which needs none of the requirements below.
+    ///
+    /// # Safety
+    ///
+    /// Same as `Guard::compare_exchange`.
+    pub unsafe fn swap<T: Send + 'static>(
+        &self,
+        ptr: &AtomicPtr<T>,
+        new: T,
+        deleter: &'static dyn Reclaim,
+    ) {
         let boxed = Box::into_raw(Box::new(new));
         let mut current = ptr.load(Ordering::Acquire);
         loop {
@@ -285,77 +1443,362 @@ impl Worker {
                 .compare_exchange(current, boxed, Ordering::Release, Ordering::Relaxed)
                 .is_ok()
             {
-                let stamp = RECENT.with(|interior| interior.borrow().stamp);
-                if stamp < count as isize {
-                    Self::rearrange(current as *mut dyn Common, deleter);
-                    break;
-                } else {
-                    let entry = ListEntry::new(current as *mut dyn Common, deleter);
-                    if let Some(e) = entry {
-                        RECENT.with(|interior| interior.borrow_mut().elements.push(e));
-                    }
-                    break;
-                }
+                self.defer_reclaim(decompose(current).0 as *mut dyn Common, deleter);
+                break;
             } else {
                 current = ptr.load(Ordering::Acquire);
             }
         }
-        self.reg.counter.set(-1);
     }
 
-    fn rearrange(ptr: *mut dyn Common, deleter: &'static dyn Reclaim) {
-        let counter = EPOCH.counter.load(Ordering::Relaxed) as isize;
-        let entry = ListEntry::new(ptr, deleter);
-        let vec = if let Some(e) = entry {
-            vec![e]
-        } else {
-            Vec::new()
-        };
-        let make_prev = RECENT.with(|interior| {
-            let mut borrowed = interior.borrow_mut();
-            borrowed.stamp = counter;
-            mem::replace(&mut borrowed.elements, vec)
-        });
-        let rec = PREVIOUS.with(|interior| {
-            let mut borrowed = interior.borrow_mut();
-            borrowed.stamp = counter - 1;
-            mem::replace(&mut borrowed.elements, make_prev)
-        });
-        //SAFETY:
-        //   Safe because the ptr is checked to be non-null
-        //   before insertion and the fact that the user
-        //   is required to uphold the safety requirements
-        //   of a ptr i.e it must be valid.
-        unsafe {
-            for element in rec {
-                element.deleter.reclaim(element.value.as_ptr());
+    /// Stores `new` only if the current value, tag included, is still
+    /// `expected`. On success the replaced pointer is retired with `deleter`
+    /// and returned. On failure nothing is retired and the value that was
+    /// found is handed back along with `new`, which was never published.
+    /// `Atomic::compare_exchange` does the same without any of the
+    /// requirements below.
+    ///
+    /// # Safety
+    ///
+    /// - Whatever non-null pointer `ptr` holds when the exchange succeeds
+    ///   must be valid to reclaim with `deleter`, e.g. it came out of
+    ///   `Box::into_raw` for `DropBox`.
+    /// - Nothing else may free or retire that pointer, it belongs to `ptr`
+    ///   until it is replaced.
+    pub unsafe fn compare_exchange<'g, T: Send + 'static>(
+        &'g self,
+        ptr: &AtomicPtr<T>,
+        expected: *mut T,
+        new: T,
+        deleter: &'static dyn Reclaim,
+    ) -> Result<Shared<'g, T>, CompareExchangeError<'g, T>> {
+        let new = Owned::new(new);
+        let raw = new.as_raw();
+        match ptr.compare_exchange(expected, raw, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(old) => {
+                mem::forget(new);
+                self.defer_reclaim(decompose(old).0 as *mut dyn Common, deleter);
+                Ok(Shared::from_raw(old))
             }
+            Err(found) => Err(CompareExchangeError {
+                current: Shared::from_raw(found),
+                new,
+            }),
         }
     }
 
-    fn try_advance() -> usize {
-        let count = EPOCH.counter.load(Ordering::Relaxed);
-        let mut current = EPOCH.registrations.head.load(Ordering::Acquire);
-        while !current.is_null() {
+    /// Keeps calling `f` with the freshly loaded value and tries to store
+    /// what it returns until a store succeeds or `f` returns None. On success
+    /// the replaced pointer is retired and returned, otherwise the value `f`
+    /// gave up on is returned as the error. See `Atomic::fetch_update` for
+    /// the safe variant.
+    ///
+    /// # Safety
+    ///
+    /// Same as `Guard::compare_exchange`.
+    pub unsafe fn fetch_update<'g, T: Send + 'static, F>(
+        &'g self,
+        ptr: &AtomicPtr<T>,
+        deleter: &'static dyn Reclaim,
+        mut f: F,
+    ) -> Result<Shared<'g, T>, Shared<'g, T>>
+    where
+        F: FnMut(Shared<'g, T>) -> Option<T>,
+    {
+        let mut current = self.load(ptr);
+        loop {
+            let Some(new) = f(current) else {
+                return Err(current);
+            };
             // SAFETY:
-            //    The operation is safe because we check the
-            //    nullability of current before dereferencing
-            //    and the the responsibility of giving a safe pointer
-            //    in this case does not rest on the user but is a part
-            //    of the implementation itself and I make sure that those
-            //    safety invariants are upheld.
-            let reg = unsafe { &(*current) };
-            let reg_counter = reg.counter.get();
-            if reg_counter < 0 || reg_counter == count as isize {
-                current = reg.next.load(Ordering::Acquire);
-            } else {
-                return count;
+            //    Upheld by the caller.
+            match unsafe { self.compare_exchange(ptr, current.as_raw(), new, deleter) } {
+                Ok(old) => return Ok(old),
+                Err(err) => current = err.current,
             }
         }
-        let ret = count + 1;
-        let _ = EPOCH
-            .counter
-            .compare_exchange(count, ret, Ordering::Relaxed, Ordering::Relaxed);
-        ret
+    }
+
+    /// Stores `new`, a pointer that is already shared, only if the current
+    /// value is still `current`. Tags are compared and stored along with
+    /// the pointers, so this both marks nodes, by passing `current` with
+    /// another tag, and unlinks them, by passing their successor. Nothing
+    /// is retired, unlinked nodes have to be handed to `retire`. On failure
+    /// the value that was found is returned.
+    pub fn compare_exchange_shared<'g, T>(
+        &'g self,
+        ptr: &AtomicPtr<T>,
+        current: Shared<'_, T>,
+        new: Shared<'_, T>,
+    ) -> Result<Shared<'g, T>, Shared<'g, T>> {
+        ptr.compare_exchange(
+            current.as_raw(),
+            new.as_raw(),
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        )
+        .map(Shared::from_raw)
+        .map_err(Shared::from_raw)
+    }
+
+    /// Sets the bits of `tag` in the tag of the pointer stored in `ptr` and
+    /// returns the previous value. Bits beyond `tag_mask` are ignored.
+    pub fn fetch_or<T>(&self, ptr: &AtomicPtr<T>, tag: usize) -> Shared<'_, T> {
+        let mut current = ptr.load(Ordering::Acquire);
+        loop {
+            let new = compose(current, decompose(current).1 | tag);
+            match ptr.compare_exchange(current, new, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(old) => return Shared::from_raw(old),
+                Err(found) => current = found,
+            }
+        }
+    }
+
+    /// Hands a pointer that was unlinked by the caller over to the garbage
+    /// lists. It is reclaimed with `deleter` once no guard can observe it,
+    /// possibly by another thread if this worker is dropped before that.
+    /// A tag in the low bits of `ptr` is stripped first, so the deleter
+    /// always sees the pointer that was allocated.
+    ///
+    /// # Safety
+    ///
+    /// - `ptr` must no longer be reachable from any shared location, so that
+    ///   only threads which were already pinned can still hold it.
+    /// - `ptr` must be retired only once.
+    /// - `deleter` must match the way `ptr` was allocated, e.g. `DropBox`
+    ///   for pointers which came out of `Box::into_raw`.
+    pub unsafe fn retire<T: Send + 'static>(&self, ptr: *mut T, deleter: &'static dyn Reclaim) {
+        self.defer_reclaim(decompose(ptr).0 as *mut dyn Common, deleter);
+    }
+
+    /// Runs `f` once every guard that is alive right now has been dropped.
+    /// Useful for cleanup which is not just freeing a pointer, such as
+    /// returning a slot to a free list.
+    pub fn defer<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.defer_entry(Some(ListEntry::Closure(Box::new(f))));
+    }
+
+    /// Hands an unlinked pointer over to the garbage lists of this thread.
+    pub(crate) fn defer_reclaim(&self, ptr: *mut dyn Common, deleter: &'static dyn Reclaim) {
+        self.defer_entry(ListEntry::new(ptr, deleter));
+    }
+
+    fn defer_entry(&self, entry: Option<ListEntry>) {
+        // The entry has been unlinked by now. Pairs with the fence in
+        // `Worker::pin`, so anyone pinned before the epoch read below
+        // becomes a blocker for advancing past it, while anyone pinned
+        // later cannot find the entry anymore.
+        atomic::fence(Ordering::SeqCst);
+        let reg = self.reg();
+        let (count, bytes) = reg.pending.get();
+        let (count, bytes) = match &entry {
+            Some(e) => {
+                let retired = reg.retired.load(Ordering::Relaxed);
+                reg.retired.store(retired + 1, Ordering::Relaxed);
+                (count + 1, bytes.saturating_add(e.size()))
+            }
+            None => (count, bytes),
+        };
+        let collector = reg.collector;
+        let due = count >= collector.retirements.load(Ordering::Relaxed)
+            || bytes >= collector.bytes.load(Ordering::Relaxed);
+        if due {
+            reg.pending.set((0, 0));
+            // A reclaimer thread advances the epoch on its own, the lists
+            // are only rotated with the epoch it published.
+            if collector.reclaimers.load(Ordering::Relaxed) == 0 {
+                collector.try_advance();
+            }
+        } else {
+            reg.pending.set((count, bytes));
+        }
+        let epoch = reg.collector.counter.load(Ordering::Acquire) as isize;
+        let mut garbage = reg.garbage.lock().unwrap();
+        if due && garbage.recent.stamp < epoch {
+            reg.rearrange(garbage, epoch, entry);
+        } else {
+            // Stamping the list later than its oldest entry only delays
+            // the reclamation of the whole list, which is always safe.
+            garbage.recent.stamp = garbage.recent.stamp.max(epoch);
+            garbage.recent.elements.extend(entry);
+            reg.publish(&garbage);
+        }
+    }
+}
+
+impl Worker {
+    fn reg(&self) -> &Registration {
+        // SAFETY:
+        //    A registration is not released while its worker is alive.
+        unsafe { self.reg.as_ref() }
+    }
+
+    /// Enters a critical section. Pinning again while a guard is alive
+    /// is cheap and does not touch the registrations.
+    pub fn pin(&self) -> Guard {
+        Registration::pin(self.reg)
+    }
+
+    /// Tries to advance the epoch and reclaims everything of this worker,
+    /// and everything orphaned, that is eligible afterwards. Meant to be
+    /// called before going idle, in tests and at shutdown, since garbage is
+    /// otherwise only reclaimed by later retirements. Garbage retired while
+    /// a guard of this worker is alive cannot be reclaimed until it is gone.
+    pub fn flush(&self) {
+        // Anything retired just now is stamped with the current epoch and
+        // needs two more to become eligible.
+        for _ in 0..2 {
+            let guard = self.pin();
+            self.reg().collector.try_advance();
+            mem::drop(guard);
+        }
+        self.reg().collect();
+    }
+
+    /// Blocks until every registration that was pinned when this was called
+    /// has unpinned or moved past the current epoch, then reclaims all the
+    /// garbage retired before the call, whichever worker retired it. The
+    /// garbage of live workers is taken out of their lists, and destructors
+    /// that other threads already started are waited for. Calls on the same
+    /// collector run one at a time.
+    ///
+    /// Panics if a guard of this worker is alive, since waiting for it
+    /// would never finish. Calling it from the destructor of something
+    /// retired never finishes either.
+    pub fn synchronize(&self) {
+        assert!(
+            self.reg().guards.get() == 0,
+            "synchronize called inside of a critical section"
+        );
+        let collector = self.reg().collector;
+        // Nothing is reclaimed while holding it, a panicking destructor
+        // leaves nothing inconsistent behind.
+        let _synchronizing = collector
+            .synchronizing
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        atomic::fence(Ordering::SeqCst);
+        let garbage = collector.take_all();
+        // Every entry that was taken is stamped with at most this epoch, and
+        // so is every registration pinned when this was called.
+        let start = collector.counter.load(Ordering::Acquire);
+        // The batches taken out before `take_all` looked may hold garbage
+        // retired before the call as well.
+        collector.batches.wait();
+        // Advancing to start + 1 needs everyone pinned at start - 1 to be
+        // gone and advancing to start + 2 does the same for start.
+        let mut round = 0;
+        while collector.counter.load(Ordering::Acquire) < start + 2 {
+            let guard = self.pin();
+            collector.try_advance();
+            mem::drop(guard);
+            if collector.counter.load(Ordering::Acquire) < start + 2 {
+                sync::backoff(round);
+                round += 1;
+            }
+        }
+        // SAFETY:
+        //    Every entry was stamped with at most `start` and the epoch is
+        //    two steps past it.
+        unsafe { collector.run(garbage) };
+    }
+
+    /// Pins the worker and loads `ptr`. The pointer stays protected for as
+    /// long as the returned `Res` lives.
+    pub fn load<'a, T>(&'a self, ptr: &AtomicPtr<T>) -> Res<'a, T> {
+        let guard = self.pin();
+        let pointer = guard.load(ptr).as_raw();
+        Res {
+            _guard: guard,
+            ptr: pointer,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The deleter parameter signifies a way the pointer that is going to be dropped.
+    /// Currently this will work as expected if the user is sure that the CAS will succeed
+    /// in the first attempt. If not so, the user must ensure that all the pointers are
+    /// constructed using a common method that is either a box or directly.
+    ///
+    /// Prefer `Atomic::swap`, which needs none of the requirements below.
+    ///
+    /// # Safety
+    ///
+    /// Same as `Guard::compare_exchange`.
+    pub unsafe fn swap<T: Send + 'static>(
+        &self,
+        ptr: &AtomicPtr<T>,
+        new: T,
+        deleter: &'static dyn Reclaim,
+    ) {
+        // SAFETY:
+        //    Upheld by the caller.
+        unsafe { self.pin().swap(ptr, new, deleter) };
+    }
+
+    /// Replaces the value only if it is still `expected`. The old value is
+    /// retired with `deleter` on success, while on failure nothing is retired
+    /// and `new` is handed back to the caller.
+    ///
+    /// # Safety
+    ///
+    /// Same as `Guard::compare_exchange`.
+    pub unsafe fn compare_exchange<T: Send + 'static>(
+        &self,
+        ptr: &AtomicPtr<T>,
+        expected: *mut T,
+        new: T,
+        deleter: &'static dyn Reclaim,
+    ) -> Result<(), T> {
+        let guard = self.pin();
+        // SAFETY:
+        //    Upheld by the caller.
+        match unsafe { guard.compare_exchange(ptr, expected, new, deleter) } {
+            Ok(_) => Ok(()),
+            Err(err) => Err(*err.new.into_box()),
+        }
+    }
+
+    /// Retires a pointer the caller has unlinked on its own, for example with
+    /// a CAS on the `next` field of a node. See `Guard::retire` for the
+    /// contract.
+    ///
+    /// # Safety
+    ///
+    /// Same as `Guard::retire`.
+    pub unsafe fn retire<T: Send + 'static>(&self, ptr: *mut T, deleter: &'static dyn Reclaim) {
+        self.pin().retire(ptr, deleter);
+    }
+
+    /// Runs `f` after a grace period. See `Guard::defer`.
+    pub fn defer<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.pin().defer(f);
+    }
+
+    /// Conditional update driven by a closure. See `Guard::fetch_update`.
+    /// Returns whether a new value was stored.
+    ///
+    /// # Safety
+    ///
+    /// Same as `Guard::compare_exchange`.
+    pub unsafe fn fetch_update<T: Send + 'static, F>(
+        &self,
+        ptr: &AtomicPtr<T>,
+        deleter: &'static dyn Reclaim,
+        f: F,
+    ) -> bool
+    where
+        F: FnMut(Shared<'_, T>) -> Option<T>,
+    {
+        let guard = self.pin();
+        // SAFETY:
+        //    Upheld by the caller.
+        unsafe { guard.fetch_update(ptr, deleter, f) }.is_ok()
     }
 }
@@ -1,3 +1,14 @@
+pub mod atomic;
+pub mod collections;
 pub mod epoch;
+mod sync;
 
-pub use crate::epoch::{DropBox, DropPointer, Registration, Worker};
+pub use crate::atomic::{Atomic, CompareExchangeError, Owned, Shared, tag_mask};
+#[cfg(feature = "debug-reclaim")]
+pub use crate::epoch::POISON;
+pub use crate::epoch::{
+    Collector, DropBox, DropPointer, Guard, Registration, Res, Stall, Stats, ThreadStats, Worker,
+    on_stall, pin, set_default_thresholds, stats, synchronize, with_worker,
+};
+#[cfg(not(loom))]
+pub use crate::epoch::{Reclaimer, spawn_reclaimer};
@@ -0,0 +1,117 @@
+//! Everything the reclamation protocol synchronizes through, along with the
+//! cells, thread locals and statics it keeps its state in. Building with
+//! `--cfg loom` swaps it for the loom versions so that the model checker
+//! can explore the interleavings and catch unsynchronized cell accesses.
+
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::{
+    self, AtomicBool, AtomicIsize, AtomicPtr, AtomicU64, AtomicUsize, Ordering,
+};
+#[cfg(loom)]
+pub(crate) use loom::sync::{Mutex, MutexGuard, RwLock};
+#[cfg(loom)]
+use loom::thread::yield_now;
+#[cfg(loom)]
+pub(crate) use loom::thread_local;
+
+#[cfg(not(loom))]
+pub(crate) use std::cell::Cell;
+#[cfg(not(loom))]
+pub(crate) use std::sync::atomic::{
+    self, AtomicBool, AtomicIsize, AtomicPtr, AtomicU64, AtomicUsize, Ordering,
+};
+#[cfg(not(loom))]
+pub(crate) use std::sync::{Mutex, MutexGuard, RwLock};
+#[cfg(not(loom))]
+use std::thread::yield_now;
+#[cfg(not(loom))]
+pub(crate) use std::thread_local;
+
+/// Loom only has an `UnsafeCell`, which tracks whether the accesses from
+/// different threads are ordered. This is the subset of `std::cell::Cell`
+/// the registrations use, built on top of it.
+#[cfg(loom)]
+pub(crate) struct Cell<T>(loom::cell::UnsafeCell<T>);
+
+#[cfg(loom)]
+impl<T: Copy> Cell<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Self(loom::cell::UnsafeCell::new(value))
+    }
+
+    pub(crate) fn get(&self) -> T {
+        // SAFETY:
+        //    Loom panics if a write can run concurrently.
+        self.0.with(|ptr| unsafe { *ptr })
+    }
+
+    pub(crate) fn set(&self, value: T) {
+        // SAFETY:
+        //    Loom panics if any other access can run concurrently.
+        self.0.with_mut(|ptr| unsafe { *ptr = value });
+    }
+}
+
+/// Waits before the next `round` of a loop that depends on other threads
+/// making progress. The first rounds only yield, later ones sleep for
+/// twice as long as the one before, up to about a millisecond.
+#[cfg(not(loom))]
+pub(crate) fn backoff(round: u32) {
+    const YIELDS: u32 = 4;
+    if round < YIELDS {
+        yield_now();
+    } else {
+        let micros = 1 << (round - YIELDS).min(10);
+        std::thread::sleep(std::time::Duration::from_micros(micros));
+    }
+}
+
+/// Loom cannot sleep, and yielding is what lets it schedule the thread
+/// that is waited for.
+#[cfg(loom)]
+pub(crate) fn backoff(_round: u32) {
+    yield_now();
+}
+
+/// Loom atomics cannot be created in a const context, so constructors
+/// which are const otherwise lose it under loom.
+macro_rules! const_fn {
+    ($(#[$attr:meta])* $vis:vis fn $($rest:tt)*) => {
+        #[cfg(not(loom))]
+        $(#[$attr])*
+        $vis const fn $($rest)*
+
+        #[cfg(loom)]
+        $(#[$attr])*
+        $vis fn $($rest)*
+    };
+}
+
+pub(crate) use const_fn;
+
+/// Defines a function returning a value that lives for the rest of the
+/// program. Loom resets its state between executions, so there the value
+/// is created lazily inside of the model. Loom also drops its lazy statics
+/// before the thread locals of threads that were joined, so the value is
+/// leaked to keep it alive for their destructors.
+macro_rules! global {
+    ($(#[$attr:meta])* $vis:vis fn $name:ident() -> &'static $ty:ty { $init:expr }) => {
+        #[cfg(not(loom))]
+        $(#[$attr])*
+        $vis fn $name() -> &'static $ty {
+            static GLOBAL: $ty = $init;
+            &GLOBAL
+        }
+
+        #[cfg(loom)]
+        $(#[$attr])*
+        $vis fn $name() -> &'static $ty {
+            loom::lazy_static! {
+                static ref GLOBAL: &'static $ty = Box::leak(Box::new($init));
+            }
+            *GLOBAL
+        }
+    };
+}
+
+pub(crate) use global;
@@ -0,0 +1,70 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{Collector, DropBox};
+    use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+    static DROPBOX: DropBox = DropBox::new();
+    const MAGIC: usize = 0x5eed_5eed;
+
+    struct Checked {
+        magic: AtomicUsize,
+    }
+
+    impl Drop for Checked {
+        fn drop(&mut self) {
+            // A reader that still holds the value after this point
+            // would see the magic go away.
+            self.magic.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn checked() -> Checked {
+        Checked {
+            magic: AtomicUsize::new(MAGIC),
+        }
+    }
+
+    #[test]
+    fn readers_never_see_reclaimed_values() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(checked())));
+        let done = AtomicBool::new(false);
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let worker = COLLECTOR.create_register();
+                    while !done.load(Ordering::Relaxed) {
+                        let guard = worker.pin();
+                        let current = guard.load(&atomic);
+                        for _ in 0..10 {
+                            // SAFETY:
+                            //    Loaded under the guard, which is still alive.
+                            let value = unsafe { current.as_ref() }.unwrap();
+                            assert_eq!(value.magic.load(Ordering::Relaxed), MAGIC);
+                        }
+                    }
+                });
+            }
+            let writers: Vec<_> = (0..4)
+                .map(|_| {
+                    s.spawn(|| {
+                        let worker = COLLECTOR.create_register();
+                        for _ in 0..2000 {
+                            // SAFETY:
+                            //    Only boxes are stored and nothing else frees them.
+                            unsafe { worker.swap(&atomic, checked(), &DROPBOX) };
+                        }
+                    })
+                })
+                .collect();
+            for writer in writers {
+                writer.join().unwrap();
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+}
@@ -0,0 +1,141 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{Atomic, Collector, Owned, Registration};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountDrops {
+        value: usize,
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn swap_and_drop() {
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let atomic = Atomic::new(CountDrops {
+            value: 0,
+            count: Arc::clone(&countdrops),
+        });
+        std::thread::scope(|s| {
+            for i in 0..15 {
+                let atomic = &atomic;
+                let countdrops = &countdrops;
+                s.spawn(move || {
+                    let worker = Registration::create_register();
+                    for j in 0..3 {
+                        let guard = worker.pin();
+                        let new = Owned::new(CountDrops {
+                            value: i * 3 + j + 1,
+                            count: Arc::clone(countdrops),
+                        });
+                        let old = atomic.swap(new, &guard);
+                        // SAFETY:
+                        //    The old value is retired but the guard is
+                        //    still alive.
+                        assert!(unsafe { old.as_ref() }.unwrap().value <= 45);
+                    }
+                });
+            }
+        });
+        std::mem::drop(atomic);
+        assert!(countdrops.load(Ordering::Relaxed) > 0);
+    }
+
+    #[test]
+    fn compare_exchange_hands_back_new() {
+        let atomic = Atomic::new(1usize);
+        let worker = Registration::create_register();
+        let guard = worker.pin();
+        let current = atomic.load(&guard);
+        let stale = current;
+        let old = atomic.compare_exchange(current, Owned::new(2), &guard);
+        assert_eq!(old.unwrap(), stale);
+
+        let err = atomic
+            .compare_exchange(stale, Owned::new(3), &guard)
+            .unwrap_err();
+        assert_eq!(*err.new, 3);
+        assert_eq!(unsafe { err.current.as_ref() }, Some(&2));
+        assert_eq!(err.current, atomic.load(&guard));
+    }
+
+    #[test]
+    fn fetch_update_counts_exactly() {
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let atomic = Atomic::new(CountDrops {
+            value: 0,
+            count: Arc::clone(&countdrops),
+        });
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    let worker = Registration::create_register();
+                    for _ in 0..50 {
+                        let guard = worker.pin();
+                        let updated = atomic.fetch_update(&guard, |current| {
+                            // SAFETY:
+                            //    The value is never null and the guard is
+                            //    alive.
+                            let value = unsafe { current.as_ref() }.unwrap().value;
+                            Some(Owned::new(CountDrops {
+                                value: value + 1,
+                                count: Arc::clone(&countdrops),
+                            }))
+                        });
+                        assert!(updated.is_ok());
+                    }
+                });
+            }
+        });
+        let worker = Registration::create_register();
+        let guard = worker.pin();
+        let last = atomic.fetch_update(&guard, |_| None).unwrap_err();
+        // SAFETY:
+        //    The guard is alive.
+        assert_eq!(unsafe { last.as_ref() }.unwrap().value, 400);
+    }
+
+    #[test]
+    fn null_atomic() {
+        let atomic: Atomic<usize> = Atomic::null();
+        let worker = Registration::create_register();
+        let guard = worker.pin();
+        assert!(atomic.load(&guard).is_null());
+        let old = atomic.swap(Owned::new(7), &guard);
+        assert!(old.is_null());
+        assert_eq!(unsafe { atomic.load(&guard).as_ref() }, Some(&7));
+    }
+
+    #[test]
+    fn get_reads_without_unsafe() {
+        let atomic: Atomic<usize> = Atomic::null();
+        let worker = Registration::create_register();
+        let guard = worker.pin();
+        assert_eq!(atomic.get(&guard), None);
+        atomic.store(Owned::new(1), &guard);
+        let first = atomic.get(&guard).unwrap();
+        atomic.store(Owned::new(2), &guard);
+        // The replaced value stays readable for as long as the guard lives.
+        assert_eq!(*first, 1);
+        assert_eq!(atomic.get(&guard), Some(&2));
+    }
+
+    #[test]
+    #[should_panic(expected = "another collector")]
+    fn guard_of_another_collector_panics() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let atomic = Atomic::new(1usize);
+        let worker = COLLECTOR.register();
+        let guard = worker.pin();
+        atomic.swap(Owned::new(2), &guard);
+    }
+}
@@ -1,6 +1,8 @@
+#![cfg(not(loom))]
+
 #[cfg(test)]
 mod tests {
-    use epoch::{DropBox, Registration};
+    use epoch::{Collector, DropBox, Registration};
     use std::sync::Arc;
     use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
 
@@ -15,6 +17,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg_attr(miri, ignore)]
     fn test() {
         let countdrops = Arc::new(AtomicUsize::new(0));
         let dup1 = Box::into_raw(Box::new(CountDrops {
@@ -37,9 +40,11 @@ mod tests {
                     let worker = Registration::create_register();
                     let res = worker.load(&atomic);
                     std::mem::drop(res);
-                    worker.swap(&atomic, dup2, &DROPBOX);
-                    worker.swap(&atomic, dup3, &DROPBOX);
-                    worker.swap(&atomic, dup4, &DROPBOX);
+                    // SAFETY:
+                    //    Only boxes are stored and nothing else frees them.
+                    unsafe { worker.swap(&atomic, dup2, &DROPBOX) };
+                    unsafe { worker.swap(&atomic, dup3, &DROPBOX) };
+                    unsafe { worker.swap(&atomic, dup4, &DROPBOX) };
                 });
             }
         });
@@ -51,4 +56,41 @@ mod tests {
 
         //println!("{}", drops);
     }
+
+    // Same as above with few enough threads for Miri to get through, while
+    // still covering concurrent swaps, orphaned garbage and registrations
+    // being reused and removed.
+    #[test]
+    fn miri_sized() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(CountDrops {
+            count: Arc::clone(&countdrops),
+        })));
+        static DROPBOX: DropBox = DropBox::new();
+        std::thread::scope(|s| {
+            for _ in 0..3 {
+                s.spawn(|| {
+                    let worker = COLLECTOR.create_register();
+                    let res = worker.load(&atomic);
+                    std::mem::drop(res);
+                    for _ in 0..2 {
+                        let new = CountDrops {
+                            count: Arc::clone(&countdrops),
+                        };
+                        // SAFETY:
+                        //    Only boxes are stored and nothing else frees them.
+                        unsafe { worker.swap(&atomic, new, &DROPBOX) };
+                    }
+                });
+            }
+        });
+        let worker = COLLECTOR.register();
+        worker.flush();
+        assert_eq!(countdrops.load(Ordering::Relaxed), 6);
+
+        std::mem::drop(worker);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
 }
@@ -0,0 +1,71 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{Collector, DropBox};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+    static DROPBOX: DropBox = DropBox::new();
+
+    struct CountDrops {
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn slow_reader_only_stalls_its_collector() {
+        static STALLED: Collector = Collector::new();
+        static HEALTHY: Collector = Collector::new();
+
+        let stalled_drops = Arc::new(AtomicUsize::new(0));
+        let healthy_drops = Arc::new(AtomicUsize::new(0));
+        let stalled = AtomicPtr::new(Box::into_raw(Box::new(CountDrops {
+            count: Arc::clone(&stalled_drops),
+        })));
+        let healthy = AtomicPtr::new(Box::into_raw(Box::new(CountDrops {
+            count: Arc::clone(&healthy_drops),
+        })));
+
+        let reader = STALLED.register();
+        let guard = reader.pin();
+
+        let stalled_writer = STALLED.register();
+        let healthy_writer = HEALTHY.register();
+        for _ in 0..10 {
+            let new = CountDrops {
+                count: Arc::clone(&stalled_drops),
+            };
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { stalled_writer.swap(&stalled, new, &DROPBOX) };
+            let new = CountDrops {
+                count: Arc::clone(&healthy_drops),
+            };
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { healthy_writer.swap(&healthy, new, &DROPBOX) };
+        }
+
+        assert_eq!(stalled_drops.load(Ordering::Relaxed), 0);
+        assert!(healthy_drops.load(Ordering::Relaxed) > 0);
+
+        std::mem::drop(guard);
+        let _ = unsafe { Box::from_raw(stalled.load(Ordering::Acquire)) };
+        let _ = unsafe { Box::from_raw(healthy.load(Ordering::Acquire)) };
+    }
+
+    #[test]
+    fn registrations_are_reused() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let first = COLLECTOR.register();
+        std::mem::drop(first);
+        assert!(COLLECTOR.find_register().is_some());
+    }
+}
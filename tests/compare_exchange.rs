@@ -0,0 +1,98 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{DropBox, Registration};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+    static DROPBOX: DropBox = DropBox::new();
+
+    struct CountDrops {
+        value: usize,
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn compare_exchange_hands_back_new() {
+        let first = Box::into_raw(Box::new(1usize));
+        let atomic = AtomicPtr::new(first);
+        let worker = Registration::create_register();
+        // SAFETY:
+        //    Only boxes are ever stored and nothing else frees them.
+        unsafe {
+            assert_eq!(
+                worker.compare_exchange(&atomic, std::ptr::null_mut(), 2, &DROPBOX),
+                Err(2)
+            );
+            assert_eq!(atomic.load(Ordering::Acquire), first);
+            assert_eq!(worker.compare_exchange(&atomic, first, 3, &DROPBOX), Ok(()));
+        }
+        let res = worker.load(&atomic);
+        assert_eq!(unsafe { *res.get_ptr() }, 3);
+        std::mem::drop(res);
+        std::mem::drop(worker);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+
+    #[test]
+    fn fetch_update_counts_exactly() {
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(CountDrops {
+            value: 0,
+            count: Arc::clone(&countdrops),
+        })));
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    let worker = Registration::create_register();
+                    for _ in 0..50 {
+                        // SAFETY:
+                        //    Only boxes are ever stored and nothing else
+                        //    frees them.
+                        let updated = unsafe {
+                            worker.fetch_update(&atomic, &DROPBOX, |current| {
+                                // SAFETY:
+                                //    The closure runs while the worker is pinned.
+                                let value = current.as_ref().unwrap().value;
+                                Some(CountDrops {
+                                    value: value + 1,
+                                    count: Arc::clone(&countdrops),
+                                })
+                            })
+                        };
+                        assert!(updated);
+                    }
+                });
+            }
+        });
+        let last = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+        assert_eq!(last.value, 400);
+    }
+
+    #[test]
+    fn fetch_update_can_give_up() {
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(10usize)));
+        let worker = Registration::create_register();
+        let guard = worker.pin();
+        // SAFETY:
+        //    Only boxes are ever stored and nothing else frees them.
+        let seen = unsafe {
+            guard.fetch_update(&atomic, &DROPBOX, |current| {
+                let value = *current.as_ref().unwrap();
+                if value > 5 { None } else { Some(value + 1) }
+            })
+        }
+        .unwrap_err();
+        assert_eq!(seen.as_ptr(), atomic.load(Ordering::Acquire));
+        std::mem::drop(guard);
+        std::mem::drop(worker);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+}
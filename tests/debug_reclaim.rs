@@ -0,0 +1,93 @@
+#![cfg(not(loom))]
+
+#[cfg(all(test, feature = "debug-reclaim"))]
+mod tests {
+    use epoch::{Collector, DropBox, POISON};
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+    static DROPBOX: DropBox = DropBox::new();
+
+    struct CountDrops {
+        count: Arc<AtomicUsize>,
+        payload: [u8; 64],
+    }
+
+    impl CountDrops {
+        fn new(count: &Arc<AtomicUsize>) -> Self {
+            Self {
+                count: Arc::clone(count),
+                payload: [7; 64],
+            }
+        }
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn stale_pointer_reads_poison() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(CountDrops::new(&countdrops))));
+        let worker = COLLECTOR.register();
+        let stale = {
+            let res = worker.load(&atomic);
+            // SAFETY:
+            //    The value is protected by the `Res`.
+            assert_eq!(unsafe { (*res.get_ptr()).payload }, [7; 64]);
+            res.get_ptr()
+        };
+        // SAFETY:
+        //    Only boxes are stored and nothing else frees them.
+        unsafe { worker.swap(&atomic, CountDrops::new(&countdrops), &DROPBOX) };
+        worker.flush();
+        worker.flush();
+        assert_eq!(countdrops.load(Ordering::Relaxed), 1);
+        // SAFETY:
+        //    The value has been dropped but its memory is still held by the
+        //    quarantine, so reading the bytes is fine.
+        let bytes = unsafe { std::ptr::read(stale as *const [u8; 8]) };
+        assert_eq!(bytes, [POISON; 8]);
+
+        // SAFETY:
+        //    Only boxes are stored and nothing else frees them.
+        unsafe { worker.swap(&atomic, CountDrops::new(&countdrops), &DROPBOX) };
+        worker.synchronize();
+        let current = atomic.swap(std::ptr::null_mut(), Ordering::Relaxed);
+        // SAFETY:
+        //    The value was never retired.
+        unsafe { drop(Box::from_raw(current)) };
+        assert_eq!(countdrops.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn loading_a_reclaimed_value_panics() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let first = Box::into_raw(Box::new(CountDrops::new(&countdrops)));
+        let atomic = AtomicPtr::new(first);
+        let worker = COLLECTOR.register();
+        // SAFETY:
+        //    Only boxes are stored and nothing else frees them.
+        unsafe { worker.swap(&atomic, CountDrops::new(&countdrops), &DROPBOX) };
+        worker.synchronize();
+        assert_eq!(countdrops.load(Ordering::Relaxed), 1);
+        // A buggy structure that still links the retired value.
+        let second = atomic.swap(first, Ordering::Relaxed);
+        let loaded = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _ = worker.load(&atomic);
+        }));
+        assert!(loaded.is_err());
+        // SAFETY:
+        //    The value was never retired.
+        unsafe { drop(Box::from_raw(second)) };
+        assert_eq!(countdrops.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,94 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{Atomic, Collector, Guard, Owned};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+    struct CountDrops {
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // Library code which protects its loads without being handed a worker.
+    fn read(atomic: &Atomic<usize>) -> usize {
+        let guard = epoch::pin();
+        *atomic.get(&guard).unwrap()
+    }
+
+    fn pinned() -> Guard {
+        epoch::pin()
+    }
+
+    #[test]
+    fn pin_without_a_worker() {
+        let atomic = Atomic::new(1usize);
+        std::thread::scope(|s| {
+            for i in 0..8 {
+                let atomic = &atomic;
+                s.spawn(move || {
+                    for _ in 0..100 {
+                        assert!(read(atomic) <= 8);
+                        let guard = pinned();
+                        atomic.store(Owned::new(i + 1), &guard);
+                    }
+                });
+            }
+        });
+        assert!(read(&atomic) <= 8);
+    }
+
+    #[test]
+    fn with_worker_reuses_the_thread_worker() {
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let atomic = Atomic::new(CountDrops {
+            count: Arc::clone(&countdrops),
+        });
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                let id = std::thread::current().id();
+                let registrations = || {
+                    epoch::stats()
+                        .threads
+                        .iter()
+                        .filter(|thread| thread.thread == id)
+                        .count()
+                };
+                for _ in 0..20 {
+                    let new = Owned::new(CountDrops {
+                        count: Arc::clone(&countdrops),
+                    });
+                    epoch::with_worker(|worker| {
+                        let guard = worker.pin();
+                        atomic.store(new, &guard);
+                    });
+                    assert_eq!(registrations(), 1);
+                }
+            });
+        });
+        std::mem::drop(atomic);
+        assert!(countdrops.load(Ordering::Relaxed) > 0);
+    }
+
+    #[test]
+    fn guard_outlives_worker() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let worker = COLLECTOR.register();
+        let guard = worker.pin();
+        std::mem::drop(worker);
+        // The guard still holds the registration.
+        assert!(COLLECTOR.find_register().is_none());
+        let mut value = 3usize;
+        let ptr = AtomicPtr::new(&mut value);
+        assert_eq!(unsafe { guard.load(&ptr).as_ref() }, Some(&3));
+        std::mem::drop(guard);
+        assert!(COLLECTOR.find_register().is_some());
+    }
+}
@@ -0,0 +1,62 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{DropBox, Registration};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+    static DROPBOX: DropBox = DropBox::new();
+
+    #[test]
+    fn deferred_closures_run_once() {
+        let ran = Arc::new(AtomicUsize::new(0));
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(0usize)));
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    let worker = Registration::create_register();
+                    for i in 0..20 {
+                        let ran = Arc::clone(&ran);
+                        worker.defer(move || {
+                            ran.fetch_add(1, Ordering::Relaxed);
+                        });
+                        // Mix pointer entries in with the closures.
+                        // SAFETY:
+                        //    Only boxes are stored and nothing else frees them.
+                        unsafe { worker.swap(&atomic, i, &DROPBOX) };
+                    }
+                });
+            }
+        });
+        // The workers are gone, what they deferred waits in the orphan queue.
+        Registration::create_register().synchronize();
+        assert_eq!(ran.load(Ordering::Relaxed), 160);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+
+    #[test]
+    fn deferred_closure_waits_for_guard() {
+        let ran = Arc::new(AtomicUsize::new(0));
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(0usize)));
+        let worker = Registration::create_register();
+        let guard = worker.pin();
+        let counter = Arc::clone(&ran);
+        guard.defer(move || {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                let other = Registration::create_register();
+                for i in 0..10 {
+                    // SAFETY:
+                    //    Only boxes are stored and nothing else frees them.
+                    unsafe { other.swap(&atomic, i, &DROPBOX) };
+                }
+            });
+        });
+        assert_eq!(ran.load(Ordering::Relaxed), 0);
+        std::mem::drop(guard);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+}
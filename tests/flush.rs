@@ -0,0 +1,91 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{Collector, DropBox};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+    static DROPBOX: DropBox = DropBox::new();
+
+    struct CountDrops {
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn flush_reclaims_everything() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(CountDrops {
+            count: Arc::clone(&countdrops),
+        })));
+        let worker = COLLECTOR.register();
+        for _ in 0..10 {
+            let new = CountDrops {
+                count: Arc::clone(&countdrops),
+            };
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { worker.swap(&atomic, new, &DROPBOX) };
+        }
+        worker.flush();
+        assert_eq!(countdrops.load(Ordering::Relaxed), 10);
+
+        std::mem::drop(worker);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+        assert_eq!(countdrops.load(Ordering::Relaxed), 11);
+    }
+
+    #[test]
+    fn flush_picks_up_orphans() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(CountDrops {
+            count: Arc::clone(&countdrops),
+        })));
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let worker = COLLECTOR.register();
+                    for _ in 0..5 {
+                        let new = CountDrops {
+                            count: Arc::clone(&countdrops),
+                        };
+                        // SAFETY:
+                        //    Only boxes are stored and nothing else frees them.
+                        unsafe { worker.swap(&atomic, new, &DROPBOX) };
+                    }
+                });
+            }
+        });
+        COLLECTOR.register().flush();
+        assert_eq!(countdrops.load(Ordering::Relaxed), 20);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+
+    #[test]
+    fn flush_respects_own_guard() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let worker = COLLECTOR.register();
+        let guard = worker.pin();
+        let counter = Arc::clone(&countdrops);
+        guard.defer(move || {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        worker.flush();
+        assert_eq!(countdrops.load(Ordering::Relaxed), 0);
+        std::mem::drop(guard);
+        worker.flush();
+        assert_eq!(countdrops.load(Ordering::Relaxed), 1);
+    }
+}
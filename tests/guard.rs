@@ -0,0 +1,85 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{DropBox, Registration};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+    struct CountDrops {
+        value: usize,
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn many_loads_under_one_pin() {
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let first = Box::into_raw(Box::new(CountDrops {
+            value: 0,
+            count: Arc::clone(&countdrops),
+        }));
+        let atomic = AtomicPtr::new(first);
+        static DROPBOX: DropBox = DropBox::new();
+        std::thread::scope(|s| {
+            for i in 0..8 {
+                let atomic = &atomic;
+                let countdrops = &countdrops;
+                s.spawn(move || {
+                    let worker = Registration::create_register();
+                    let guard = worker.pin();
+                    let seen = guard.load(atomic);
+                    for _ in 0..100 {
+                        // SAFETY:
+                        //    Nothing loaded through the guard can be
+                        //    reclaimed while the guard is alive.
+                        let value = unsafe { seen.as_ref() }.unwrap().value;
+                        assert!(value <= 8);
+                        let _ = guard.load(atomic);
+                    }
+                    let new = CountDrops {
+                        value: i + 1,
+                        count: Arc::clone(countdrops),
+                    };
+                    // SAFETY:
+                    //    Only boxes are stored and nothing else frees them.
+                    unsafe { guard.swap(atomic, new, &DROPBOX) };
+                    // The value we loaded first is still readable after
+                    // swapping it out in the same critical section.
+                    let _ = unsafe { seen.as_ref() }.unwrap().value;
+                });
+            }
+        });
+        let last = atomic.load(Ordering::Acquire);
+        // SAFETY:
+        //    All the workers are gone and the pointer came from a Box.
+        let _ = unsafe { Box::from_raw(last) };
+        assert!(countdrops.load(Ordering::Relaxed) > 0);
+    }
+
+    #[test]
+    fn nested_pins_and_res() {
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(5usize)));
+        let worker = Registration::create_register();
+        {
+            let outer = worker.pin();
+            let inner = worker.pin();
+            let res = worker.load(&atomic);
+            // Dropping the Res must not unpin the outer guards.
+            std::mem::drop(res);
+            std::mem::drop(inner);
+            let shared = outer.load(&atomic);
+            assert!(!shared.is_null());
+            assert_eq!(unsafe { *shared.as_ref().unwrap() }, 5);
+        }
+        let res = worker.load(&atomic);
+        assert_eq!(unsafe { *res.get_ptr() }, 5);
+        std::mem::drop(res);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+}
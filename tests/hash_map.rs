@@ -0,0 +1,156 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::collections::HashMap;
+    use std::hash::{BuildHasher, Hasher};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Barrier};
+
+    struct CountDrops {
+        id: usize,
+        count: Arc<AtomicUsize>,
+    }
+
+    impl CountDrops {
+        fn new(id: usize, count: &Arc<AtomicUsize>) -> Self {
+            Self {
+                id,
+                count: Arc::clone(count),
+            }
+        }
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // Sends every key to the same hash, so all entries end up in one run
+    // of the list which has to be told apart by the keys alone.
+    #[derive(Default)]
+    struct Collide;
+
+    impl BuildHasher for Collide {
+        type Hasher = Collide;
+
+        fn build_hasher(&self) -> Collide {
+            Collide
+        }
+    }
+
+    impl Hasher for Collide {
+        fn finish(&self) -> u64 {
+            42
+        }
+
+        fn write(&mut self, _: &[u8]) {}
+    }
+
+    #[test]
+    fn reads_under_one_pin_while_growing() {
+        let map = HashMap::new();
+        for i in 0..1000 {
+            assert!(map.insert(i, i * 2));
+        }
+        assert!(!map.insert(7, 0));
+        assert_eq!(map.len(), 1000);
+        let guard = epoch::pin();
+        for i in 0..1000 {
+            assert_eq!(map.get(&i, &guard), Some(&(i * 2)));
+        }
+        assert_eq!(map.get(&1000, &guard), None);
+        let seven = map.get(&7, &guard).unwrap();
+        assert!(map.remove(&7));
+        assert!(!map.remove(&7));
+        // Still readable, the guard holds off its reclamation.
+        assert_eq!(*seven, 14);
+        drop(guard);
+        assert!(!map.contains_key(&7));
+        assert_eq!(map.len(), 999);
+    }
+
+    #[test]
+    fn colliding_hashes() {
+        let map: HashMap<String, usize, Collide> = HashMap::default();
+        for i in 0..20 {
+            assert!(map.insert(i.to_string(), i));
+        }
+        assert!(!map.insert("3".to_string(), 0));
+        for i in (0..20).step_by(2) {
+            assert!(map.remove(i.to_string().as_str()));
+        }
+        let guard = epoch::pin();
+        for i in 0..20 {
+            let expected = (i % 2 == 1).then_some(&i);
+            assert_eq!(map.get(i.to_string().as_str(), &guard), expected);
+        }
+    }
+
+    // Every thread inserts every key, so each key is inserted once and all
+    // other values are dropped right away. Then the even keys are removed,
+    // split between the threads. Every value must be dropped exactly once:
+    // the rejected ones right away, the removed ones once they are
+    // reclaimed and the rest when the map goes away.
+    fn stress(threads: usize, keys: usize) {
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let map = HashMap::new();
+        let barrier = Barrier::new(threads);
+        let (inserted, removed) = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..threads)
+                .map(|t| {
+                    let (map, barrier, countdrops) = (&map, &barrier, &countdrops);
+                    s.spawn(move || {
+                        let mut inserted = 0;
+                        let mut removed = 0;
+                        for i in 0..keys {
+                            let key = if t % 2 == 0 { i } else { keys - 1 - i };
+                            let value = CountDrops::new(key, countdrops);
+                            inserted += map.insert(key, value) as usize;
+                        }
+                        barrier.wait();
+                        let guard = epoch::pin();
+                        for key in 0..keys {
+                            let value = map.get(&key, &guard).unwrap();
+                            assert_eq!(value.id, key);
+                        }
+                        drop(guard);
+                        barrier.wait();
+                        for key in (0..keys).filter(|k| k % 2 == 0 && k / 2 % threads == t) {
+                            removed += map.remove(&key) as usize;
+                            assert!(!map.contains_key(&key));
+                        }
+                        (inserted, removed)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap())
+                .fold((0, 0), |(a, b), (c, d)| (a + c, b + d))
+        });
+        assert_eq!(inserted, keys);
+        assert_eq!(removed, keys.div_ceil(2));
+        assert_eq!(map.len(), keys - removed);
+        for key in 0..keys {
+            assert_eq!(map.contains_key(&key), key % 2 == 1);
+        }
+        epoch::synchronize();
+        let rejected = (threads - 1) * keys;
+        assert_eq!(countdrops.load(Ordering::Relaxed), rejected + removed);
+        drop(map);
+        assert_eq!(countdrops.load(Ordering::Relaxed), threads * keys);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn concurrent_insert_remove() {
+        stress(4, 5000);
+    }
+
+    #[test]
+    fn miri_sized() {
+        stress(2, 8);
+    }
+}
@@ -0,0 +1,149 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::Collector;
+    use epoch::collections::ListSet;
+    use std::cmp::Ordering as Cmp;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Barrier};
+
+    struct CountDrops {
+        id: usize,
+        count: Arc<AtomicUsize>,
+    }
+
+    impl CountDrops {
+        fn new(id: usize, count: &Arc<AtomicUsize>) -> Self {
+            Self {
+                id,
+                count: Arc::clone(count),
+            }
+        }
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    impl PartialEq for CountDrops {
+        fn eq(&self, other: &Self) -> bool {
+            self.id == other.id
+        }
+    }
+
+    impl Eq for CountDrops {}
+
+    impl PartialOrd for CountDrops {
+        fn partial_cmp(&self, other: &Self) -> Option<Cmp> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for CountDrops {
+        fn cmp(&self, other: &Self) -> Cmp {
+            self.id.cmp(&other.id)
+        }
+    }
+
+    impl std::borrow::Borrow<usize> for CountDrops {
+        fn borrow(&self) -> &usize {
+            &self.id
+        }
+    }
+
+    #[test]
+    fn sorted_unique_and_borrowed_for_the_pin() {
+        let set = ListSet::new();
+        for id in [5, 1, 3, 1, 4] {
+            set.insert(id);
+        }
+        assert!(!set.insert(3));
+        assert!(set.contains(&4));
+        assert!(!set.contains(&2));
+        let guard = epoch::pin();
+        let five = set.get(&5, &guard).unwrap();
+        assert!(set.remove(&5));
+        assert!(!set.remove(&5));
+        // Still readable, the guard holds off its reclamation.
+        assert_eq!(*five, 5);
+        drop(guard);
+        assert!(!set.contains(&5));
+        assert!(set.insert(2));
+        let guard = epoch::pin();
+        let found: Vec<_> = (0..6).filter_map(|i| set.get(&i, &guard)).collect();
+        assert_eq!(found, [&1, &2, &3, &4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "another collector")]
+    fn guard_of_another_collector_panics() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let set = ListSet::new();
+        set.insert(1);
+        let worker = COLLECTOR.register();
+        let guard = worker.pin();
+        let _ = set.get(&1, &guard);
+    }
+
+    // Every thread inserts every id, so each id is inserted once and all
+    // other attempts are handed back and dropped. Then the even ids are
+    // removed, split between the threads. Every value must be dropped
+    // exactly once: the rejected ones right away, the removed ones once
+    // they are reclaimed and the rest when the set goes away.
+    fn stress(threads: usize, ids: usize) {
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let set = ListSet::new();
+        let barrier = Barrier::new(threads);
+        let (inserted, removed) = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..threads)
+                .map(|t| {
+                    let (set, barrier, countdrops) = (&set, &barrier, &countdrops);
+                    s.spawn(move || {
+                        let mut inserted = 0;
+                        let mut removed = 0;
+                        // Go in different directions to get more contention.
+                        for i in 0..ids {
+                            let id = if t % 2 == 0 { i } else { ids - 1 - i };
+                            inserted += set.insert(CountDrops::new(id, countdrops)) as usize;
+                        }
+                        barrier.wait();
+                        for id in (0..ids).filter(|id| id % 2 == 0 && id / 2 % threads == t) {
+                            removed += set.remove(&id) as usize;
+                            assert!(!set.contains(&id));
+                        }
+                        (inserted, removed)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap())
+                .fold((0, 0), |(a, b), (c, d)| (a + c, b + d))
+        });
+        assert_eq!(inserted, ids);
+        assert_eq!(removed, ids.div_ceil(2));
+        for id in 0..ids {
+            assert_eq!(set.contains(&id), id % 2 == 1);
+        }
+        epoch::synchronize();
+        let rejected = (threads - 1) * ids;
+        assert_eq!(countdrops.load(Ordering::Relaxed), rejected + removed);
+        drop(set);
+        assert_eq!(countdrops.load(Ordering::Relaxed), threads * ids);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn concurrent_insert_remove() {
+        stress(4, 500);
+    }
+
+    #[test]
+    fn miri_sized() {
+        stress(2, 6);
+    }
+}
@@ -0,0 +1,130 @@
+// Run with
+//
+//     LOOM_MAX_PREEMPTIONS=2 RUSTFLAGS="--cfg loom" cargo test --release --test loom
+//
+// Without a preemption bound the models take a very long time.
+#![cfg(loom)]
+
+use epoch::{Atomic, Collector, DropBox, Owned, Registration};
+use loom::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use loom::thread;
+
+static DROPBOX: DropBox = DropBox::new();
+
+// Every value knows which flag to raise when it is dropped. Readers find
+// the flag by comparing pointers, so checking it never touches the value.
+struct Value {
+    freed: &'static AtomicBool,
+}
+
+impl Drop for Value {
+    fn drop(&mut self) {
+        self.freed.store(true, Ordering::Release);
+    }
+}
+
+fn leak<T>(value: T) -> &'static T {
+    Box::leak(Box::new(value))
+}
+
+fn values(n: usize) -> Vec<(*mut Value, &'static AtomicBool)> {
+    (0..n)
+        .map(|_| {
+            let freed = leak(AtomicBool::new(false));
+            (Box::into_raw(Box::new(Value { freed })), freed)
+        })
+        .collect()
+}
+
+#[test]
+fn load_never_sees_freed_value() {
+    loom::model(|| {
+        // An `Atomic` only takes guards of the default collector, which
+        // loom builds anew for every execution.
+        let values = values(3);
+        // SAFETY:
+        //    Every value came out of a Box and is published only once, so
+        //    the addresses stay the ones recorded in `values`.
+        let owned = |ptr: *mut Value| Owned::from(unsafe { Box::from_raw(ptr) });
+        let atomic = leak(Atomic::from(owned(values[0].0)));
+        let seen: Vec<_> = values
+            .iter()
+            .map(|&(ptr, freed)| (ptr as usize, freed))
+            .collect();
+        let news: Vec<_> = values[1..].iter().map(|&(ptr, _)| ptr as usize).collect();
+
+        let reader = thread::spawn(move || {
+            let worker = Registration::create_register();
+            let guard = worker.pin();
+            let ptr = atomic.load(&guard).as_ptr() as usize;
+            let (_, freed) = seen.iter().find(|(p, _)| *p == ptr).unwrap();
+            assert!(!freed.load(Ordering::Acquire));
+        });
+
+        let writer = Registration::create_register();
+        for new in news {
+            let guard = writer.pin();
+            atomic.swap(owned(new as *mut Value), &guard);
+        }
+        writer.flush();
+        reader.join().unwrap();
+    });
+}
+
+#[test]
+fn guard_protects_across_advances() {
+    loom::model(|| {
+        let collector = leak(Collector::new());
+        let values = values(2);
+        let atomic = leak(AtomicPtr::new(values[0].0));
+        let (old, freed) = (values[0].0 as usize, values[0].1);
+        let new = values[1].0 as usize;
+
+        let reader = thread::spawn(move || {
+            let worker = collector.register();
+            let guard = worker.pin();
+            let first = guard.load(atomic).as_ptr() as usize;
+            thread::yield_now();
+            let second = guard.load(atomic).as_ptr() as usize;
+            if first == old || second == old {
+                assert!(!freed.load(Ordering::Acquire));
+            }
+        });
+
+        let writer = collector.register();
+        // SAFETY:
+        //    The value came out of a Box and is only published here.
+        let new = unsafe { Box::from_raw(new as *mut Value) };
+        // SAFETY:
+        //    Only boxes are stored and nothing else frees them.
+        unsafe { writer.pin().swap(atomic, *new, &DROPBOX) };
+        writer.flush();
+        writer.flush();
+        reader.join().unwrap();
+    });
+}
+
+#[test]
+fn default_worker_in_model() {
+    loom::model(|| {
+        let values = values(2);
+        let atomic = leak(AtomicPtr::new(values[0].0));
+        let (old, freed) = (values[0].0 as usize, values[0].1);
+        let new = values[1].0 as usize;
+
+        let reader = thread::spawn(move || {
+            let guard = epoch::pin();
+            if guard.load(atomic).as_ptr() as usize == old {
+                assert!(!freed.load(Ordering::Acquire));
+            }
+        });
+
+        // SAFETY:
+        //    The value came out of a Box and is only published here.
+        let new = unsafe { Box::from_raw(new as *mut Value) };
+        // SAFETY:
+        //    Only boxes are stored and nothing else frees them.
+        unsafe { epoch::pin().swap(atomic, *new, &DROPBOX) };
+        reader.join().unwrap();
+    });
+}
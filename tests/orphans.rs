@@ -0,0 +1,61 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{Collector, DropBox};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+    static DROPBOX: DropBox = DropBox::new();
+
+    struct CountDrops {
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn garbage_of_exited_threads_is_reclaimed() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(CountDrops {
+            count: Arc::clone(&countdrops),
+        })));
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    let worker = COLLECTOR.create_register();
+                    for _ in 0..5 {
+                        let new = CountDrops {
+                            count: Arc::clone(&countdrops),
+                        };
+                        // SAFETY:
+                        //    Only boxes are stored and nothing else frees them.
+                        unsafe { worker.swap(&atomic, new, &DROPBOX) };
+                    }
+                });
+            }
+        });
+
+        // Every worker is gone by now, so the surviving thread has to pick
+        // their garbage up from the orphan queue.
+        let survivor = COLLECTOR.create_register();
+        for _ in 0..10 {
+            let new = CountDrops {
+                count: Arc::clone(&countdrops),
+            };
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { survivor.swap(&atomic, new, &DROPBOX) };
+        }
+        assert!(countdrops.load(Ordering::Relaxed) >= 40);
+
+        std::mem::drop(survivor);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+}
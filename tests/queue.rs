@@ -0,0 +1,112 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::collections::Queue;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountDrops {
+        producer: usize,
+        seq: usize,
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // Producers push `per_producer` values each while consumers pop until
+    // everything has come out. Every value must come out exactly once, in
+    // the order its producer pushed it, and be dropped exactly once.
+    fn stress(producers: usize, consumers: usize, per_producer: usize) {
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let queue = Queue::new();
+        let total = producers * per_producer;
+        let taken = AtomicUsize::new(0);
+        let mut popped: Vec<(usize, usize)> = std::thread::scope(|s| {
+            for producer in 0..producers {
+                let queue = &queue;
+                let countdrops = &countdrops;
+                s.spawn(move || {
+                    for seq in 0..per_producer {
+                        queue.push(CountDrops {
+                            producer,
+                            seq,
+                            count: Arc::clone(countdrops),
+                        });
+                    }
+                });
+            }
+            let handles: Vec<_> = (0..consumers)
+                .map(|_| {
+                    let queue = &queue;
+                    let taken = &taken;
+                    s.spawn(move || {
+                        let mut last = vec![None; producers];
+                        let mut popped = Vec::new();
+                        while taken.load(Ordering::Relaxed) < total {
+                            let Some(value) = queue.pop() else {
+                                std::thread::yield_now();
+                                continue;
+                            };
+                            taken.fetch_add(1, Ordering::Relaxed);
+                            let previous = last[value.producer].replace(value.seq);
+                            assert!(previous < Some(value.seq));
+                            popped.push((value.producer, value.seq));
+                        }
+                        popped
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect()
+        });
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop().map(|v| v.seq), None);
+        popped.sort_unstable();
+        let expected: Vec<_> = (0..producers)
+            .flat_map(|p| (0..per_producer).map(move |seq| (p, seq)))
+            .collect();
+        assert_eq!(popped, expected);
+        assert_eq!(countdrops.load(Ordering::Relaxed), total);
+        // Freeing the retired sentinels must not drop any value again.
+        epoch::synchronize();
+        assert_eq!(countdrops.load(Ordering::Relaxed), total);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn concurrent_push_pop() {
+        stress(4, 4, 2000);
+    }
+
+    #[test]
+    fn miri_sized() {
+        stress(2, 2, 3);
+    }
+
+    #[test]
+    fn fifo_and_drop_frees_what_is_left() {
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let queue = Queue::new();
+        assert!(queue.is_empty());
+        for seq in 0..10 {
+            queue.push(CountDrops {
+                producer: 0,
+                seq,
+                count: Arc::clone(&countdrops),
+            });
+        }
+        assert!(!queue.is_empty());
+        assert_eq!(queue.pop().map(|v| v.seq), Some(0));
+        assert_eq!(queue.pop().map(|v| v.seq), Some(1));
+        assert_eq!(countdrops.load(Ordering::Relaxed), 2);
+        drop(queue);
+        assert_eq!(countdrops.load(Ordering::Relaxed), 10);
+    }
+}
@@ -0,0 +1,124 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{Collector, DropBox};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+    use std::thread::{self, ThreadId};
+    use std::time::{Duration, Instant};
+
+    static DROPBOX: DropBox = DropBox::new();
+
+    struct CountDrops {
+        count: Arc<AtomicUsize>,
+        caller: ThreadId,
+        inline: Arc<AtomicBool>,
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            if thread::current().id() == self.caller {
+                self.inline.store(true, Ordering::Relaxed);
+            }
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn wait_for(count: &AtomicUsize, expected: usize) -> bool {
+        let start = Instant::now();
+        while count.load(Ordering::Relaxed) < expected {
+            if start.elapsed() > Duration::from_secs(10) {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        true
+    }
+
+    #[test]
+    fn destructors_run_on_the_reclaimer() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let inline = Arc::new(AtomicBool::new(false));
+        let caller = thread::current().id();
+        let new = || CountDrops {
+            count: Arc::clone(&countdrops),
+            caller,
+            inline: Arc::clone(&inline),
+        };
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(new())));
+        let reclaimer = COLLECTOR.spawn_reclaimer(Duration::from_millis(1), 8);
+        let worker = COLLECTOR.register();
+        for _ in 0..100 {
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { worker.swap(&atomic, new(), &DROPBOX) };
+            // Retiring does not advance the epoch anymore, the lists are
+            // only handed over once the reclaimer did.
+            thread::sleep(Duration::from_millis(1));
+        }
+        // Everything but the two lists of the worker is handed over.
+        assert!(wait_for(&countdrops, 90));
+        assert!(!inline.load(Ordering::Relaxed));
+
+        reclaimer.stop();
+        let before = countdrops.load(Ordering::Relaxed);
+        for _ in 0..10 {
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { worker.swap(&atomic, new(), &DROPBOX) };
+        }
+        assert!(countdrops.load(Ordering::Relaxed) > before);
+        assert!(inline.load(Ordering::Relaxed));
+
+        std::mem::drop(worker);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+
+    #[test]
+    fn retiring_leaves_advancing_to_the_reclaimer() {
+        static COLLECTOR: Collector = Collector::new();
+
+        COLLECTOR.set_thresholds(1, usize::MAX);
+        let reclaimer = COLLECTOR.spawn_reclaimer(Duration::from_secs(3600), 1);
+        // Wait for the first round, the next one is an hour away.
+        let start = Instant::now();
+        while {
+            let stats = COLLECTOR.stats();
+            stats.advances + stats.failed_advances == 0
+        } {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(1));
+        }
+        let before = COLLECTOR.stats();
+
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(0usize)));
+        let worker = COLLECTOR.register();
+        for i in 1..=10 {
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { worker.swap(&atomic, i, &DROPBOX) };
+        }
+        let after = COLLECTOR.stats();
+        assert_eq!(after.epoch, before.epoch);
+        assert_eq!(after.advances, before.advances);
+        assert_eq!(after.failed_advances, before.failed_advances);
+
+        reclaimer.stop();
+        worker.flush();
+        std::mem::drop(worker);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+
+    #[test]
+    fn reclaimer_stops_when_dropped() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let reclaimer = COLLECTOR.spawn_reclaimer(Duration::from_secs(3600), 1);
+        let start = Instant::now();
+        std::mem::drop(reclaimer);
+        assert!(start.elapsed() < Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,62 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{Collector, DropBox};
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+    static DROPBOX: DropBox = DropBox::new();
+    const MAGIC: usize = 0xfeed_beef;
+
+    struct Checked {
+        magic: AtomicUsize,
+    }
+
+    impl Drop for Checked {
+        fn drop(&mut self) {
+            self.magic.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn checked() -> Checked {
+        Checked {
+            magic: AtomicUsize::new(MAGIC),
+        }
+    }
+
+    #[test]
+    fn registrations_come_and_go() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(checked())));
+        std::thread::scope(|s| {
+            for t in 0..8 {
+                let atomic = &atomic;
+                s.spawn(move || {
+                    for i in 0..300 {
+                        // Mix fresh registrations, which are removed once
+                        // enough of them are free, with reused ones.
+                        let worker = if (t + i) % 2 == 0 {
+                            COLLECTOR.create_register()
+                        } else {
+                            COLLECTOR.register()
+                        };
+                        let guard = worker.pin();
+                        let current = guard.load(atomic);
+                        let value = unsafe { current.as_ref() }.unwrap();
+                        assert_eq!(value.magic.load(Ordering::Relaxed), MAGIC);
+                        // SAFETY:
+                        //    Only boxes are stored and nothing else frees them.
+                        unsafe { guard.swap(atomic, checked(), &DROPBOX) };
+                        assert_eq!(value.magic.load(Ordering::Relaxed), MAGIC);
+                        if i % 3 == 0 {
+                            // Let the guard outlive the worker now and then.
+                            std::mem::drop(worker);
+                        }
+                    }
+                });
+            }
+        });
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+}
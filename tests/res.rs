@@ -0,0 +1,76 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{DropBox, Registration};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+    static DROPBOX: DropBox = DropBox::new();
+
+    struct CountDrops {
+        value: usize,
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn reads_through_res() {
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let atomic = AtomicPtr::<CountDrops>::new(std::ptr::null_mut());
+        let worker = Registration::create_register();
+        let res = worker.load(&atomic);
+        // SAFETY:
+        //    The pointer is null.
+        assert!(unsafe { res.as_ref() }.is_none());
+        drop(res);
+
+        // SAFETY:
+        //    Only boxes are stored and nothing else frees them.
+        unsafe {
+            worker.swap(
+                &atomic,
+                CountDrops {
+                    value: 0,
+                    count: Arc::clone(&countdrops),
+                },
+                &DROPBOX,
+            )
+        };
+        std::thread::scope(|s| {
+            for i in 1..=4 {
+                let atomic = &atomic;
+                let countdrops = &countdrops;
+                s.spawn(move || {
+                    let worker = Registration::create_register();
+                    for _ in 0..100 {
+                        let res = worker.load(atomic);
+                        // SAFETY:
+                        //    Only boxes are stored and they are retired
+                        //    through the default collector.
+                        let value = unsafe { res.as_ref() }.unwrap().value;
+                        assert!(value <= 4);
+                    }
+                    let new = CountDrops {
+                        value: i,
+                        count: Arc::clone(countdrops),
+                    };
+                    // SAFETY:
+                    //    Only boxes are stored and nothing else frees them.
+                    unsafe { worker.swap(atomic, new, &DROPBOX) };
+                });
+            }
+        });
+        worker.synchronize();
+        assert_eq!(countdrops.load(Ordering::Relaxed), 4);
+        // SAFETY:
+        //    Every thread is done and the last value was never retired.
+        unsafe { drop(Box::from_raw(atomic.load(Ordering::Relaxed))) };
+        assert_eq!(countdrops.load(Ordering::Relaxed), 5);
+    }
+}
@@ -0,0 +1,89 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{DropBox, Registration};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+    static DROPBOX: DropBox = DropBox::new();
+
+    struct CountDrops {
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    struct Node {
+        _value: CountDrops,
+        next: AtomicPtr<Node>,
+    }
+
+    #[test]
+    fn retire_unlinked_nodes() {
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let head = AtomicPtr::new(std::ptr::null_mut());
+        for _ in 0..100 {
+            let node = Box::into_raw(Box::new(Node {
+                _value: CountDrops {
+                    count: Arc::clone(&countdrops),
+                },
+                next: AtomicPtr::new(head.load(Ordering::Relaxed)),
+            }));
+            head.store(node, Ordering::Relaxed);
+        }
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let worker = Registration::create_register();
+                    loop {
+                        let guard = worker.pin();
+                        let current = guard.load(&head);
+                        let Some(node) = (unsafe { current.as_ref() }) else {
+                            break;
+                        };
+                        let next = node.next.load(Ordering::Acquire);
+                        if head
+                            .compare_exchange(
+                                current.as_ptr(),
+                                next,
+                                Ordering::AcqRel,
+                                Ordering::Acquire,
+                            )
+                            .is_ok()
+                        {
+                            // SAFETY:
+                            //    The node was unlinked by the CAS above and it
+                            //    came out of a Box.
+                            unsafe { guard.retire(current.as_ptr(), &DROPBOX) };
+                        }
+                    }
+                });
+            }
+        });
+        assert!(head.load(Ordering::Relaxed).is_null());
+        // The workers are gone, their garbage waits in the orphan queue.
+        Registration::create_register().synchronize();
+        assert_eq!(countdrops.load(Ordering::Relaxed), 100);
+    }
+
+    #[test]
+    fn retire_through_worker() {
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let worker = Registration::create_register();
+        for _ in 0..10 {
+            let ptr = Box::into_raw(Box::new(CountDrops {
+                count: Arc::clone(&countdrops),
+            }));
+            // SAFETY:
+            //    The pointer was never shared and came out of a Box.
+            unsafe { worker.retire(ptr, &DROPBOX) };
+        }
+        worker.synchronize();
+        assert_eq!(countdrops.load(Ordering::Relaxed), 10);
+    }
+}
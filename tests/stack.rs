@@ -0,0 +1,91 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::collections::Stack;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountDrops {
+        id: usize,
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // Every thread pushes `per_thread` values and pops half as many, then
+    // whatever is left is popped at the end. Each value must come out
+    // exactly once and be dropped exactly once.
+    fn stress(threads: usize, per_thread: usize) {
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let stack = Stack::new();
+        let mut popped: Vec<usize> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..threads)
+                .map(|t| {
+                    let stack = &stack;
+                    let countdrops = &countdrops;
+                    s.spawn(move || {
+                        let mut popped = Vec::new();
+                        for i in 0..per_thread {
+                            stack.push(CountDrops {
+                                id: t * per_thread + i,
+                                count: Arc::clone(countdrops),
+                            });
+                            if i % 2 == 1 {
+                                popped.push(stack.pop().unwrap().id);
+                            }
+                        }
+                        popped
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect()
+        });
+        assert_eq!(countdrops.load(Ordering::Relaxed), popped.len());
+        while let Some(value) = stack.pop() {
+            popped.push(value.id);
+        }
+        assert!(stack.is_empty());
+        popped.sort_unstable();
+        assert_eq!(popped, (0..threads * per_thread).collect::<Vec<_>>());
+        assert_eq!(countdrops.load(Ordering::Relaxed), threads * per_thread);
+        // Freeing the retired nodes must not drop any value again.
+        epoch::synchronize();
+        assert_eq!(countdrops.load(Ordering::Relaxed), threads * per_thread);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn concurrent_push_pop() {
+        stress(8, 2000);
+    }
+
+    #[test]
+    fn miri_sized() {
+        stress(3, 4);
+    }
+
+    #[test]
+    fn drop_frees_what_is_left() {
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let stack = Stack::new();
+        for id in 0..10 {
+            stack.push(CountDrops {
+                id,
+                count: Arc::clone(&countdrops),
+            });
+        }
+        assert_eq!(stack.pop().map(|v| v.id), Some(9));
+        assert_eq!(stack.pop().map(|v| v.id), Some(8));
+        assert_eq!(countdrops.load(Ordering::Relaxed), 2);
+        drop(stack);
+        assert_eq!(countdrops.load(Ordering::Relaxed), 10);
+    }
+}
@@ -0,0 +1,141 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{Collector, DropBox, Stall};
+    use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    static DROPBOX: DropBox = DropBox::new();
+
+    #[test]
+    fn reports_stalled_reader() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let stalls = Arc::new(Mutex::new(Vec::<Stall>::new()));
+        let reported = Arc::clone(&stalls);
+        COLLECTOR.on_stall(Duration::from_millis(20), move |stall| {
+            reported.lock().unwrap().push(stall.clone());
+        });
+
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(0usize)));
+        let pinned = AtomicBool::new(false);
+        let release = AtomicBool::new(false);
+        thread::scope(|s| {
+            let reader = thread::Builder::new()
+                .name("slow-reader".into())
+                .spawn_scoped(s, || {
+                    let worker = COLLECTOR.register();
+                    let res = worker.load(&atomic);
+                    pinned.store(true, Ordering::Release);
+                    while !release.load(Ordering::Acquire) {
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                    std::mem::drop(res);
+                })
+                .unwrap();
+            while !pinned.load(Ordering::Acquire) {
+                std::hint::spin_loop();
+            }
+            let writer = COLLECTOR.register();
+            for i in 1..=50 {
+                // SAFETY:
+                //    Only boxes are stored and nothing else frees them.
+                unsafe { writer.swap(&atomic, i, &DROPBOX) };
+                thread::sleep(Duration::from_millis(2));
+            }
+            release.store(true, Ordering::Release);
+            let id = reader.thread().id();
+            reader.join().unwrap();
+
+            let stalls = stalls.lock().unwrap();
+            // Reported once, even though every swap found it again.
+            assert_eq!(stalls.len(), 1);
+            assert_eq!(stalls[0].thread, id);
+            assert_eq!(stalls[0].name.as_deref(), Some("slow-reader"));
+            assert_eq!(stalls[0].pinned + 1, stalls[0].epoch);
+            assert!(stalls[0].stalled_for >= Duration::from_millis(20));
+            writer.flush();
+        });
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+
+    #[test]
+    fn reports_every_stalled_reader() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let stalls = Arc::new(Mutex::new(Vec::<Stall>::new()));
+        let reported = Arc::clone(&stalls);
+        COLLECTOR.on_stall(Duration::from_millis(20), move |stall| {
+            reported.lock().unwrap().push(stall.clone());
+        });
+
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(0usize)));
+        let pinned = AtomicUsize::new(0);
+        let release = AtomicBool::new(false);
+        thread::scope(|s| {
+            for name in ["first-reader", "second-reader"] {
+                thread::Builder::new()
+                    .name(name.into())
+                    .spawn_scoped(s, || {
+                        let worker = COLLECTOR.register();
+                        let res = worker.load(&atomic);
+                        pinned.fetch_add(1, Ordering::Release);
+                        while !release.load(Ordering::Acquire) {
+                            thread::sleep(Duration::from_millis(1));
+                        }
+                        std::mem::drop(res);
+                    })
+                    .unwrap();
+            }
+            // Both readers pin before anything advances the epoch, so both
+            // of them hold it back.
+            while pinned.load(Ordering::Acquire) != 2 {
+                std::hint::spin_loop();
+            }
+            let writer = COLLECTOR.register();
+            for i in 1..=50 {
+                // SAFETY:
+                //    Only boxes are stored and nothing else frees them.
+                unsafe { writer.swap(&atomic, i, &DROPBOX) };
+                thread::sleep(Duration::from_millis(2));
+            }
+            release.store(true, Ordering::Release);
+        });
+        let mut names: Vec<_> = stalls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|stall| stall.name.clone().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["first-reader", "second-reader"]);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+
+    #[test]
+    fn quick_readers_are_not_reported() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let stalled = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&stalled);
+        COLLECTOR.on_stall(Duration::from_secs(60), move |_| {
+            flag.store(true, Ordering::Relaxed);
+        });
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(0usize)));
+        let reader = COLLECTOR.register();
+        let writer = COLLECTOR.register();
+        let guard = reader.pin();
+        for i in 1..=10 {
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { writer.swap(&atomic, i, &DROPBOX) };
+        }
+        std::mem::drop(guard);
+        assert!(!stalled.load(Ordering::Relaxed));
+        std::mem::drop((reader, writer));
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+}
@@ -0,0 +1,103 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{Collector, DropBox};
+    use std::sync::atomic::{AtomicPtr, Ordering};
+
+    static DROPBOX: DropBox = DropBox::new();
+
+    #[test]
+    fn counts_retired_and_reclaimed() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(0usize)));
+        let worker = COLLECTOR.register();
+        for i in 1..=10 {
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { worker.swap(&atomic, i, &DROPBOX) };
+        }
+        let stats = COLLECTOR.stats();
+        assert_eq!(stats.retired, 10);
+        assert_eq!(stats.live_registrations, 1);
+        assert_eq!(stats.free_registrations, 0);
+        assert_eq!(stats.threads.len(), 1);
+        assert_eq!(stats.threads[0].thread, std::thread::current().id());
+        let pending = stats.threads[0].recent + stats.threads[0].previous;
+        assert_eq!(stats.reclaimed + pending, 10);
+        assert!(stats.advances > 0);
+
+        worker.flush();
+        let stats = COLLECTOR.stats();
+        assert_eq!(stats.reclaimed, 10);
+        assert_eq!(stats.threads[0].recent, 0);
+        assert_eq!(stats.threads[0].previous, 0);
+
+        std::mem::drop(worker);
+        let stats = COLLECTOR.stats();
+        assert_eq!(stats.live_registrations, 0);
+        assert_eq!(stats.free_registrations, 1);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+
+    #[test]
+    fn shows_a_stuck_epoch() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(0usize)));
+        let reader = COLLECTOR.register();
+        let writer = COLLECTOR.register();
+        let guard = reader.pin();
+        for i in 1..=10 {
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { writer.swap(&atomic, i, &DROPBOX) };
+        }
+        let stats = COLLECTOR.stats();
+        assert_eq!(stats.reclaimed, 0);
+        assert!(stats.failed_advances > 0);
+        assert!(stats.epoch <= 1);
+        let pinned = stats.threads.iter().filter(|t| t.pinned.is_some()).count();
+        assert_eq!(pinned, 1);
+
+        std::mem::drop(guard);
+        writer.flush();
+        assert_eq!(COLLECTOR.stats().reclaimed, 10);
+        std::mem::drop((reader, writer));
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+
+    #[test]
+    fn names_the_threads() {
+        static COLLECTOR: Collector = Collector::new();
+
+        std::thread::Builder::new()
+            .name("stats-worker".into())
+            .spawn(|| {
+                let _worker = COLLECTOR.register();
+                let stats = COLLECTOR.stats();
+                assert_eq!(stats.threads[0].thread, std::thread::current().id());
+                assert_eq!(stats.threads[0].name.as_deref(), Some("stats-worker"));
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn leaves_registrations_out() {
+        static COLLECTOR: Collector = Collector::new();
+
+        // Only a few registrations are kept for reuse, the rest are removed
+        // and freed through the orphan queue.
+        let workers: Vec<_> = (0..8).map(|_| COLLECTOR.create_register()).collect();
+        std::mem::drop(workers);
+        let worker = COLLECTOR.register();
+        worker.synchronize();
+        let stats = COLLECTOR.stats();
+        assert_eq!(stats.orphaned, 0);
+        assert_eq!(stats.retired, 0);
+        assert_eq!(stats.reclaimed, 0);
+    }
+}
@@ -0,0 +1,106 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{Collector, DropBox};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    static DROPBOX: DropBox = DropBox::new();
+
+    struct CountDrops {
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn synchronize_waits_for_readers() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(CountDrops {
+            count: Arc::clone(&countdrops),
+        })));
+        let pinned = AtomicBool::new(false);
+        let released = AtomicBool::new(false);
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                let reader = COLLECTOR.register();
+                let guard = reader.pin();
+                let _ = guard.load(&atomic);
+                pinned.store(true, Ordering::Release);
+                std::thread::sleep(Duration::from_millis(100));
+                released.store(true, Ordering::Release);
+                std::mem::drop(guard);
+            });
+            while !pinned.load(Ordering::Acquire) {
+                std::hint::spin_loop();
+            }
+            let writer = COLLECTOR.register();
+            let new = CountDrops {
+                count: Arc::clone(&countdrops),
+            };
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { writer.swap(&atomic, new, &DROPBOX) };
+            writer.synchronize();
+            // The reader must be gone and the old value destroyed.
+            assert!(released.load(Ordering::Acquire));
+            assert_eq!(countdrops.load(Ordering::Relaxed), 1);
+        });
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+
+    #[test]
+    fn synchronize_on_default_collector() {
+        let ran = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&ran);
+        epoch::pin().defer(move || flag.store(true, Ordering::Relaxed));
+        epoch::synchronize();
+        assert!(ran.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn synchronize_reclaims_garbage_of_other_workers() {
+        // The lists of the other worker are never rotated on their own.
+        static COLLECTOR: Collector = Collector::with_thresholds(usize::MAX, usize::MAX);
+
+        let ran = Arc::new(AtomicUsize::new(0));
+        let other = COLLECTOR.register();
+        for _ in 0..3 {
+            let ran = Arc::clone(&ran);
+            other.defer(move || {
+                ran.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+        let worker = COLLECTOR.register();
+        worker.synchronize();
+        assert_eq!(ran.load(Ordering::Relaxed), 3);
+        assert_eq!(
+            COLLECTOR
+                .stats()
+                .threads
+                .iter()
+                .map(|t| t.recent)
+                .sum::<usize>(),
+            0
+        );
+        std::mem::drop(other);
+    }
+
+    #[test]
+    #[should_panic(expected = "critical section")]
+    fn synchronize_inside_critical_section_panics() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let worker = COLLECTOR.register();
+        let _guard = worker.pin();
+        worker.synchronize();
+    }
+}
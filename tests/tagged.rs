@@ -0,0 +1,122 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{Atomic, DropBox, Owned, Registration, tag_mask};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+    static DROPBOX: DropBox = DropBox::new();
+
+    struct CountDrops {
+        value: usize,
+        count: Arc<AtomicUsize>,
+    }
+
+    impl CountDrops {
+        fn new(value: usize, count: &Arc<AtomicUsize>) -> Self {
+            Self {
+                value,
+                count: Arc::clone(count),
+            }
+        }
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn atomic_keeps_and_compares_tags() {
+        assert_eq!(tag_mask::<CountDrops>(), 7);
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let atomic = Atomic::new(CountDrops::new(1, &countdrops));
+        let worker = Registration::create_register();
+        {
+            let guard = worker.pin();
+            let untagged = atomic.load(&guard);
+            assert_eq!(untagged.tag(), 0);
+            assert_eq!(atomic.fetch_or(1, &guard), untagged);
+            let marked = atomic.load(&guard);
+            assert_eq!(marked.tag(), 1);
+            assert_eq!(marked.as_ptr(), untagged.as_ptr());
+            assert_ne!(marked, untagged);
+            // SAFETY:
+            //    The value has not been retired.
+            assert_eq!(unsafe { marked.as_ref() }.unwrap().value, 1);
+
+            let new = Owned::new(CountDrops::new(2, &countdrops));
+            let err = atomic.compare_exchange(untagged, new, &guard).unwrap_err();
+            assert_eq!(err.current, marked);
+            let old = atomic.compare_exchange(marked, err.new, &guard).unwrap();
+            assert_eq!(old.tag(), 1);
+            atomic.fetch_or(2, &guard);
+            assert_eq!(atomic.load(&guard).tag(), 2);
+        }
+        worker.synchronize();
+        assert_eq!(countdrops.load(Ordering::Relaxed), 1);
+        // The tagged pointer that is still stored is freed untagged.
+        drop(atomic);
+        assert_eq!(countdrops.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn mark_unlink_and_retire_tagged_pointers() {
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let second = Box::into_raw(Box::new(CountDrops::new(2, &countdrops)));
+        let first = Box::into_raw(Box::new(CountDrops::new(1, &countdrops)));
+        // `next` of the first node, kept outside of it to keep things short.
+        let next = AtomicPtr::new(second);
+        let head = AtomicPtr::new(first);
+        let worker = Registration::create_register();
+        {
+            let guard = worker.pin();
+            // Logically delete the first node by marking its successor.
+            let succ = guard.fetch_or(&next, 1);
+            assert_eq!(succ.tag(), 0);
+            let marked = guard.load(&next);
+            assert_eq!(marked.tag(), 1);
+
+            // Unlink it, the successor goes into the head untagged.
+            let current = guard.load(&head);
+            assert_eq!(
+                guard.compare_exchange_shared(&head, current, marked),
+                Ok(current)
+            );
+            let unlinked = guard.load(&head);
+            assert_eq!(unlinked, marked);
+            let cleaned = unlinked.with_tag(0);
+            assert!(
+                guard
+                    .compare_exchange_shared(&head, cleaned, cleaned)
+                    .is_err()
+            );
+            assert!(
+                guard
+                    .compare_exchange_shared(&head, unlinked, cleaned)
+                    .is_ok()
+            );
+            assert_eq!(guard.load(&head).as_raw(), second);
+
+            // Retiring a tagged pointer strips the tag first.
+            // SAFETY:
+            //    The first node is unreachable and retired once.
+            unsafe { guard.retire(current.with_tag(3).as_raw(), &DROPBOX) };
+        }
+        worker.synchronize();
+        assert_eq!(countdrops.load(Ordering::Relaxed), 1);
+
+        let res = worker.load(&next);
+        assert_eq!(res.tag(), 1);
+        // SAFETY:
+        //    The second node was never retired.
+        assert_eq!(unsafe { res.as_ref() }.unwrap().value, 2);
+        drop(res);
+        // SAFETY:
+        //    The second node was never retired.
+        unsafe { drop(Box::from_raw(second)) };
+        assert_eq!(countdrops.load(Ordering::Relaxed), 2);
+    }
+}
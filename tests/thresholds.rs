@@ -0,0 +1,142 @@
+#![cfg(not(loom))]
+
+#[cfg(test)]
+mod tests {
+    use epoch::{Collector, DropBox};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+    static DROPBOX: DropBox = DropBox::new();
+
+    struct CountDrops {
+        count: Arc<AtomicUsize>,
+        _payload: [u8; 256],
+    }
+
+    impl CountDrops {
+        fn new(count: &Arc<AtomicUsize>) -> Self {
+            Self {
+                count: Arc::clone(count),
+                _payload: [0; 256],
+            }
+        }
+    }
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn reclaims_after_retirement_threshold() {
+        static COLLECTOR: Collector = Collector::with_thresholds(64, usize::MAX);
+
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(CountDrops::new(&countdrops))));
+        let worker = COLLECTOR.register();
+        // Hitting the threshold the first time moves everything retired
+        // before it to the previous list, hitting it again reclaims it.
+        for _ in 0..127 {
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { worker.swap(&atomic, CountDrops::new(&countdrops), &DROPBOX) };
+        }
+        assert_eq!(countdrops.load(Ordering::Relaxed), 0);
+        // SAFETY:
+        //    Only boxes are stored and nothing else frees them.
+        unsafe { worker.swap(&atomic, CountDrops::new(&countdrops), &DROPBOX) };
+        assert_eq!(countdrops.load(Ordering::Relaxed), 63);
+
+        worker.flush();
+        assert_eq!(countdrops.load(Ordering::Relaxed), 128);
+        std::mem::drop(worker);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+
+    #[test]
+    fn reclaims_after_byte_threshold() {
+        static COLLECTOR: Collector = Collector::with_thresholds(usize::MAX, 4096);
+
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(CountDrops::new(&countdrops))));
+        let worker = COLLECTOR.register();
+        for _ in 0..8 {
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { worker.swap(&atomic, CountDrops::new(&countdrops), &DROPBOX) };
+        }
+        assert_eq!(countdrops.load(Ordering::Relaxed), 0);
+        for _ in 0..64 {
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { worker.swap(&atomic, CountDrops::new(&countdrops), &DROPBOX) };
+        }
+        assert!(countdrops.load(Ordering::Relaxed) > 0);
+
+        std::mem::drop(worker);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+
+    #[test]
+    fn thresholds_can_be_changed() {
+        static COLLECTOR: Collector = Collector::new();
+
+        COLLECTOR.set_thresholds(usize::MAX, usize::MAX);
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(CountDrops::new(&countdrops))));
+        let worker = COLLECTOR.register();
+        for _ in 0..3 {
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { worker.swap(&atomic, CountDrops::new(&countdrops), &DROPBOX) };
+        }
+        assert_eq!(countdrops.load(Ordering::Relaxed), 0);
+        assert_eq!(COLLECTOR.stats().advances, 0);
+
+        COLLECTOR.set_thresholds(1, usize::MAX);
+        for _ in 0..3 {
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { worker.swap(&atomic, CountDrops::new(&countdrops), &DROPBOX) };
+        }
+        assert_eq!(COLLECTOR.stats().advances, 3);
+        std::mem::drop(worker);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+
+    #[test]
+    fn default_thresholds_can_be_set() {
+        // No other test in this file uses the default collector.
+        epoch::set_default_thresholds(64, usize::MAX);
+        let ran = Arc::new(AtomicUsize::new(0));
+        let before = epoch::stats().advances;
+        for _ in 0..63 {
+            let ran = Arc::clone(&ran);
+            epoch::pin().defer(move || {
+                ran.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+        assert_eq!(epoch::stats().advances, before);
+        epoch::synchronize();
+        assert_eq!(ran.load(Ordering::Relaxed), 63);
+    }
+
+    #[test]
+    fn default_collector_advances_on_every_retirement() {
+        static COLLECTOR: Collector = Collector::new();
+
+        let countdrops = Arc::new(AtomicUsize::new(0));
+        let atomic = AtomicPtr::new(Box::into_raw(Box::new(CountDrops::new(&countdrops))));
+        let worker = COLLECTOR.register();
+        for _ in 0..3 {
+            // SAFETY:
+            //    Only boxes are stored and nothing else frees them.
+            unsafe { worker.swap(&atomic, CountDrops::new(&countdrops), &DROPBOX) };
+        }
+        assert_eq!(countdrops.load(Ordering::Relaxed), 1);
+
+        std::mem::drop(worker);
+        let _ = unsafe { Box::from_raw(atomic.load(Ordering::Acquire)) };
+    }
+}